@@ -0,0 +1,35 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rustupolis::tuple;
+use rustupolis::tuple::{Tuple, E};
+use rustupolis_server::lexing::{format_tuples, parse_tuples};
+
+/// Builds the parameters of an `out` request carrying `size` sensor readings.
+fn out_batch(size: usize) -> String {
+    let tuples: Vec<Tuple> = (0..size)
+        .map(|index| {
+            tuple![
+                E::str("température"),
+                E::I(index as i32),
+                E::D(index as f64 / 10.0),
+                E::T(tuple![E::str("site \"A\""), E::I(2)])
+            ]
+        })
+        .collect();
+    format_tuples(&tuples)
+}
+
+/// The time per tuple must stay flat when the batch grows, parsing is linear in the request size.
+fn parse_out_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_out_batch");
+    for size in [100, 1_000, 10_000, 100_000] {
+        let request = out_batch(size);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &request, |b, request| {
+            b.iter(|| parse_tuples(black_box(request)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse_out_batch);
+criterion_main!(benches);
@@ -0,0 +1,59 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rustupolis::store::{SimpleStore, Store};
+use rustupolis::tuple;
+use rustupolis::tuple::{Tuple, E};
+use rustupolis_server::indexed_store::IndexedStore;
+use rustupolis_server::store::TupleStore;
+
+/// Returns `size` readings of 100 sensors.
+fn readings(size: usize) -> impl Iterator<Item = Tuple> {
+    (0..size).map(|index| {
+        tuple![
+            E::str(format!("sensor {}", index % 100)),
+            E::I(index as i32),
+            E::D(index as f64 / 10.0)
+        ]
+    })
+}
+
+/// Compares `SimpleStore` with an `IndexedStore` keyed on the given field.
+fn bench_rdp(c: &mut Criterion, name: &str, template: Tuple, key_field: usize) {
+    let mut group = c.benchmark_group(name);
+    for size in [1_000, 10_000, 100_000] {
+        let mut simple = SimpleStore::new();
+        for tuple in readings(size) {
+            Store::out(&mut simple, tuple).unwrap();
+        }
+        group.bench_with_input(
+            BenchmarkId::new("simple", size),
+            &template,
+            |b, template| b.iter(|| Store::rdp(&mut simple, black_box(template)).unwrap()),
+        );
+        let mut indexed = IndexedStore::new(key_field);
+        for tuple in readings(size) {
+            indexed.out(tuple).unwrap();
+        }
+        group.bench_with_input(
+            BenchmarkId::new("indexed", size),
+            &template,
+            |b, template| b.iter(|| indexed.rdp(black_box(template))),
+        );
+    }
+    group.finish();
+}
+
+/// Reads the last reading of a sensor, where `SimpleStore` can already narrow its scan to the
+/// tuples starting with the sensor name.
+fn rdp_first_field(c: &mut Criterion) {
+    let template = tuple![E::str("sensor 99"), E::I(999), E::Any];
+    bench_rdp(c, "rdp_first_field", template, 0);
+}
+
+/// Reads a reading by its index, where `SimpleStore` has to scan every tuple.
+fn rdp_second_field(c: &mut Criterion) {
+    let template = tuple![E::Any, E::I(999), E::Any];
+    bench_rdp(c, "rdp_second_field", template, 1);
+}
+
+criterion_group!(benches, rdp_first_field, rdp_second_field);
+criterion_main!(benches);
@@ -1,5 +1,6 @@
 use rustupolis::tuple;
 use rustupolis::tuple::E;
+use rustupolis_server::aggregation::Aggregation;
 use rustupolis_server::client::Client;
 
 fn main() {
@@ -11,6 +12,7 @@ fn main() {
     let tuple_space_name_mean = String::from("tuple_space_mean");
     let attribute = String::from("admin");
     let key = "an_example_very_";
+    let token = "admin_token";
 
     client.connect(
         String::from("127.0.0.1"),
@@ -27,6 +29,9 @@ fn main() {
         key,
     );
 
+    client.authenticate(&server_tcp_name, token);
+    client.authenticate(&server_udp_name, token);
+
     client.create(
         &server_tcp_name,
         vec![attribute.clone()],
@@ -50,31 +55,24 @@ fn main() {
         tuple![E::str("temp"), E::I(20),],
     ]);
 
-    let mut data = client.in_instr(vec![
-        tuple![E::str("temp"), E::Any],
-        tuple![E::str("temp"), E::Any],
-        tuple![E::str("temp"), E::Any],
-        tuple![E::str("temp"), E::Any],
-        tuple![E::str("temp"), E::Any],
-    ]);
-    let mut sum = 0;
-    let mut nb_tuple = 0;
-
-    while !data.is_empty() {
-        if let E::T(tuple) = data.first() {
-            if let E::I(nbr) = tuple.rest().first() {
-                sum += nbr;
-                nb_tuple += 1;
-                data = data.rest();
-            }
-        }
-    }
+    // The server computes the mean of the temperatures and takes them out of the tuple space.
+    let result = client.aggregate_in(Aggregation::Mean(1), None, tuple![E::str("temp"), E::Any]);
+    let mean = match result.is_empty() {
+        false => match result.first() {
+            E::D(mean) => *mean,
+            _ => f64::NAN,
+        },
+        true => f64::NAN,
+    };
 
-    let mean:f64 = (sum) as f64 / (nb_tuple) as f64;
-
-    client.in_instr(vec![tuple![E::str("state"),E::Any]]);
-    client.out(vec![tuple!(E::str("state"),E::str("state"))]);
-    client.in_instr(vec![tuple![E::Any]]);
+    // Take the state tuple and write the new one at once, so that other clients never see the
+    // tuple space without a state.
+    client.out(vec![tuple!(E::str("state"),E::str("collecting"))]);
+    client.transaction(vec![
+        ("in", vec![tuple![E::str("state"),E::Any]]),
+        ("out", vec![tuple!(E::str("state"),E::str("done"))]),
+    ]);
+    client.in_instr(vec![tuple![E::Any,E::Any]]);
 
     client.attach(&server_udp_name, vec![attribute.clone()], &tuple_space_name_mean);
 
@@ -1,8 +1,10 @@
 use rustupolis::tuple;
 use rustupolis::tuple::{E, Tuple};
+use rustupolis_server::managed_space::SpaceLimits;
 use rustupolis_server::repository::Repository;
 use rustupolis_server::server::{Protocol, Server};
 use rustupolis_server::server_launcher::ServerLauncher;
+use rustupolis_server::store::StoreKind;
 
 fn main() {
     let ip_address = String::from("127.0.0.1");
@@ -12,7 +14,9 @@ fn main() {
     let repository = Repository::new("admin");
     let key = "an_example_very_";
 
-    repository.add_tuple_space(String::from("DATA"),vec![String::from("admin")]);
+    repository.add_credential("admin_token", vec![String::from("admin")]);
+
+    repository.add_tuple_space(String::from("DATA"),vec![String::from("admin")],StoreKind::default(),SpaceLimits::unlimited()).unwrap();
 
     repository.add_tuple_to_tuple_space(String::from("DATA"), Tuple::new(&[E::str("test")]));
     repository.remove_tuple_to_tuple_space(String::from("DATA"), Tuple::new(&[E::Any]));
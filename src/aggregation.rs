@@ -0,0 +1,153 @@
+//! Module Aggregation
+//!
+//! Aggregates computed by the server over the tuples matching a template, so that clients do not
+//! have to pull every tuple to count or average them.
+
+use std::collections::BTreeMap;
+
+use rustupolis::tuple::{Tuple, E};
+
+use crate::codec::tuple_elements;
+use crate::constant::{COUNT, FIELD, GROUP, MAX, MEAN, MIN, SUM};
+
+/// An aggregate function, with the position of the field it applies to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aggregation {
+    Count,
+    Sum(usize),
+    Min(usize),
+    Max(usize),
+    Mean(usize),
+}
+
+impl Aggregation {
+    /// Returns the aggregation matching the name used by the `aggregate` command. Every function
+    /// but `count` needs a field.
+    pub fn from_name(name: &str, field: Option<usize>) -> Option<Aggregation> {
+        match name {
+            COUNT => Some(Aggregation::Count),
+            SUM => Some(Aggregation::Sum(field?)),
+            MIN => Some(Aggregation::Min(field?)),
+            MAX => Some(Aggregation::Max(field?)),
+            MEAN => Some(Aggregation::Mean(field?)),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aggregation::Count => COUNT,
+            Aggregation::Sum(_) => SUM,
+            Aggregation::Min(_) => MIN,
+            Aggregation::Max(_) => MAX,
+            Aggregation::Mean(_) => MEAN,
+        }
+    }
+
+    pub fn field(&self) -> Option<usize> {
+        match self {
+            Aggregation::Count => None,
+            Aggregation::Sum(field)
+            | Aggregation::Min(field)
+            | Aggregation::Max(field)
+            | Aggregation::Mean(field) => Some(*field),
+        }
+    }
+
+    /// Writes the aggregation as the parameters of the `aggregate` command, before the template.
+    pub fn to_parameters(&self, group: Option<usize>) -> String {
+        let mut parameters = String::from(self.name());
+        if let Some(field) = self.field() {
+            parameters += &format!(" {}={}", FIELD, field);
+        }
+        if let Some(group) = group {
+            parameters += &format!(" {}={}", GROUP, group);
+        }
+        parameters
+    }
+
+    /// Aggregates the tuples. Without grouping the result is a tuple holding the value, with
+    /// grouping it holds a `(group, value)` tuple for each value of the group field, in order.
+    /// Only integers and floats are aggregated, and `None` is returned when there is nothing to
+    /// aggregate but for `count`.
+    pub fn aggregate(&self, tuples: &[Tuple], group: Option<usize>) -> Option<Tuple> {
+        match group {
+            None => Some(Tuple::new(&[self.aggregate_values(tuples)?])),
+            Some(group) => {
+                let mut groups: BTreeMap<E, Vec<Tuple>> = BTreeMap::new();
+                for tuple in tuples {
+                    if let Some(key) = tuple_elements(tuple).get(group) {
+                        groups.entry(key.clone()).or_default().push(tuple.clone());
+                    }
+                }
+                let results: Vec<E> = groups
+                    .into_iter()
+                    .filter_map(|(key, tuples)| {
+                        let value = self.aggregate_values(&tuples)?;
+                        Some(E::T(Tuple::new(&[key, value])))
+                    })
+                    .collect();
+                if results.is_empty() && *self != Aggregation::Count {
+                    None
+                } else {
+                    Some(Tuple::from_vec(results))
+                }
+            }
+        }
+    }
+
+    fn aggregate_values(&self, tuples: &[Tuple]) -> Option<E> {
+        let field = match self.field() {
+            None => return Some(E::I(tuples.len() as i32)),
+            Some(field) => field,
+        };
+        let values: Vec<E> = tuples
+            .iter()
+            .filter_map(|tuple| match tuple_elements(tuple).get(field) {
+                Some(value @ (E::I(_) | E::D(_))) => Some(value.clone()),
+                _ => None,
+            })
+            .collect();
+        let first = values.first()?.clone();
+        match self {
+            Aggregation::Sum(_) => Some(sum(&values)),
+            Aggregation::Min(_) => Some(values.into_iter().fold(first, |min, value| {
+                if number(&value) < number(&min) {
+                    value
+                } else {
+                    min
+                }
+            })),
+            Aggregation::Max(_) => Some(values.into_iter().fold(first, |max, value| {
+                if number(&value) > number(&max) {
+                    value
+                } else {
+                    max
+                }
+            })),
+            _ => Some(E::D(
+                values.iter().map(number).sum::<f64>() / values.len() as f64,
+            )),
+        }
+    }
+}
+
+fn number(value: &E) -> f64 {
+    match value {
+        E::I(value) => *value as f64,
+        E::D(value) => *value,
+        _ => f64::NAN,
+    }
+}
+
+/// Sums the values as an integer when they are all integers and the sum does not overflow.
+fn sum(values: &[E]) -> E {
+    let integers: Option<i32> = values.iter().try_fold(0i32, |sum, value| match value {
+        E::I(value) => sum.checked_add(*value),
+        _ => None,
+    });
+    match integers {
+        Some(sum) => E::I(sum),
+        None => E::D(values.iter().map(number).sum()),
+    }
+}
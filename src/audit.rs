@@ -0,0 +1,245 @@
+//! Module Audit
+//!
+//! Append-only log of the requests received by the repository: when, from which peer and
+//! protocol, with which attributes, the command, the tuple space it targets and whether it was
+//! allowed, denied or failed. The log is a file of JSON lines, renamed with the suffix `.1` once
+//! it reaches its maximum size while the previous files are shifted to `.2` and so on, the oldest
+//! being removed.
+//!
+//! The log never holds the payload of the requests, neither the tuples nor the tokens.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rustupolis::tuple::{Tuple, E};
+use serde_json::{json, Value};
+
+pub const ALLOW: &str = "allow";
+pub const DENY: &str = "deny";
+pub const ERROR: &str = "error";
+
+/// One request of the log.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditRecord {
+    pub timestamp: f64,
+    pub peer: Option<String>,
+    pub protocol: Option<String>,
+    pub attributes: Vec<String>,
+    pub command: String,
+    pub tuple_space: Option<String>,
+    /// `ALLOW`, `DENY` or `ERROR`.
+    pub result: String,
+    /// The error returned, if the request was denied or failed.
+    pub message: Option<String>,
+}
+
+impl AuditRecord {
+    /// Creates the record of a request received now.
+    pub fn new(
+        peer: Option<String>,
+        protocol: Option<String>,
+        attributes: Vec<String>,
+        command: String,
+        tuple_space: Option<String>,
+        result: &str,
+        message: Option<String>,
+    ) -> AuditRecord {
+        AuditRecord {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0.0, |duration| duration.as_secs_f64()),
+            peer,
+            protocol,
+            attributes,
+            command,
+            tuple_space,
+            result: String::from(result),
+            message,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "timestamp": self.timestamp,
+            "peer": self.peer,
+            "protocol": self.protocol,
+            "attributes": self.attributes,
+            "command": self.command,
+            "tuple_space": self.tuple_space,
+            "result": self.result,
+            "message": self.message,
+        })
+    }
+
+    pub fn from_json(value: &Value) -> Result<AuditRecord, String> {
+        let text = |key: &str| value.get(key).and_then(Value::as_str).map(String::from);
+        Ok(AuditRecord {
+            timestamp: value
+                .get("timestamp")
+                .and_then(Value::as_f64)
+                .ok_or_else(|| String::from("missing timestamp"))?,
+            peer: text("peer"),
+            protocol: text("protocol"),
+            attributes: value
+                .get("attributes")
+                .and_then(Value::as_array)
+                .map(|attributes| {
+                    attributes
+                        .iter()
+                        .filter_map(|attribute| attribute.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            command: text("command").ok_or_else(|| String::from("missing command"))?,
+            tuple_space: text("tuple_space"),
+            result: text("result").ok_or_else(|| String::from("missing result"))?,
+            message: text("message"),
+        })
+    }
+
+    /// Returns the record as a response of the `audit` command: `(timestamp, peer, protocol,
+    /// (attributes), command, tuple_space, result, message)`, the missing fields being `nil`.
+    pub fn to_tuple(&self) -> Tuple {
+        let optional = |value: &Option<String>| match value {
+            Some(value) => E::str(value),
+            None => E::None,
+        };
+        Tuple::new(&[
+            E::D(self.timestamp),
+            optional(&self.peer),
+            optional(&self.protocol),
+            E::T(Tuple::from_vec(
+                self.attributes.iter().map(E::str).collect(),
+            )),
+            E::str(&self.command),
+            optional(&self.tuple_space),
+            E::str(&self.result),
+            optional(&self.message),
+        ])
+    }
+}
+
+struct AuditFile {
+    file: File,
+    size: u64,
+}
+
+pub struct AuditLog {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: Mutex<AuditFile>,
+}
+
+impl AuditLog {
+    /// Opens the log for appending, creating it and its directory if needed. The log is rotated
+    /// once it reaches `max_bytes`, keeping `max_files` previous files.
+    pub fn open(path: &Path, max_bytes: u64, max_files: usize) -> Result<AuditLog, String> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(|error| error.to_string())?;
+        }
+        let file = AuditLog::append(path)?;
+        let size = file.metadata().map_err(|error| error.to_string())?.len();
+        Ok(AuditLog {
+            path: path.to_path_buf(),
+            max_bytes,
+            max_files,
+            file: Mutex::new(AuditFile { file, size }),
+        })
+    }
+
+    fn append(path: &Path) -> Result<File, String> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|error| error.to_string())
+    }
+
+    /// The path of a previous file, `0` being the current one.
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        match index {
+            0 => self.path.clone(),
+            _ => {
+                let mut path = self.path.clone().into_os_string();
+                path.push(format!(".{}", index));
+                PathBuf::from(path)
+            }
+        }
+    }
+
+    /// Appends the record, rotating the log first if it would exceed its maximum size.
+    pub fn record(&self, record: &AuditRecord) -> Result<(), String> {
+        let mut line = record.to_json().to_string();
+        line.push('\n');
+        let mut current = self.file.lock().unwrap();
+        if current.size > 0 && current.size + line.len() as u64 > self.max_bytes {
+            current.file.flush().map_err(|error| error.to_string())?;
+            for index in (0..self.max_files).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    fs::rename(&from, self.rotated_path(index + 1))
+                        .map_err(|error| error.to_string())?;
+                }
+            }
+            // Without previous files to keep, the log starts over.
+            if self.max_files == 0 {
+                fs::remove_file(&self.path).map_err(|error| error.to_string())?;
+            }
+            current.file = AuditLog::append(&self.path)?;
+            current.size = 0;
+        }
+        current
+            .file
+            .write_all(line.as_bytes())
+            .map_err(|error| error.to_string())?;
+        current.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Returns the records passing the filter, the oldest first, at most `limit` of the most
+    /// recent ones if any, and the number of malformed lines skipped.
+    pub fn query<F>(
+        &self,
+        filter: F,
+        limit: Option<usize>,
+    ) -> Result<(Vec<AuditRecord>, usize), String>
+    where
+        F: Fn(&AuditRecord) -> bool,
+    {
+        // Holds the lock so that the files are not rotated while they are read.
+        let _current = self.file.lock().unwrap();
+        let mut records = Vec::new();
+        let mut malformed = 0;
+        for index in (0..=self.max_files).rev() {
+            let path = self.rotated_path(index);
+            if !path.exists() {
+                continue;
+            }
+            let log = fs::read_to_string(&path).map_err(|error| error.to_string())?;
+            for line in log.lines().filter(|line| !line.trim().is_empty()) {
+                let record = serde_json::from_str::<Value>(line)
+                    .map_err(|error| error.to_string())
+                    .and_then(|value| AuditRecord::from_json(&value));
+                let record = match record {
+                    Ok(record) => record,
+                    Err(_) => {
+                        malformed += 1;
+                        continue;
+                    }
+                };
+                if filter(&record) {
+                    records.push(record);
+                }
+            }
+        }
+        if let Some(limit) = limit {
+            let skipped = records.len().saturating_sub(limit);
+            records.drain(..skipped);
+        }
+        Ok((records, malformed))
+    }
+}
@@ -1,9 +1,20 @@
-use crate::constant::{ATTACH, CREATE, DELETE, ERROR, IN, OK, OUT, READ, SPACE};
-use crate::lexing::Lexer;
+use crate::aggregation::Aggregation;
+use crate::codec::{encode_tuples, Encoding};
+use crate::constant::{
+    ACL, AGGREGATE, AGGREGATE_IN, ATTACH, AUDIT, AUTH, AUTHENTICATED, COPY, CREATE, DELETE,
+    DELETE_NAMESPACE, DESCRIBE, ENCODING, ENCODING_UPDATED, EXPORT, FORMAT, GRANT, IMPORT, IN,
+    LIST, METADATA, MOVE, OK, OPTION_PREFIX, OUT, PREFIX, PROVENANCE, READ, SET_DESCRIPTION, SPACE,
+    SUBSCRIBE, TRANSACTION, TTL, UNSUBSCRIBE,
+};
+use crate::dump::DumpFormat;
+use crate::lexing::{format_tuples, parse_tuples};
+use crate::managed_space::SpaceLimits;
 use crate::server_client::ServerClient;
-use rustupolis::tuple::E::S;
-use rustupolis::tuple::{Tuple, E};
+use crate::store::StoreKind;
+use rustupolis::tuple::Tuple;
 use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
 
 pub struct Client {
     server_list: HashMap<String, ServerClient>,
@@ -74,6 +85,78 @@ impl Client {
         }
     }
 
+    /// Creates a tuple space with the given store and capacity limits, see `StoreKind` and
+    /// `SpaceLimits`.
+    pub fn create_with_options(
+        &self,
+        server_name: &str,
+        attributes: Vec<String>,
+        tuple_space_name: &str,
+        admin_attribute: &str,
+        store: &StoreKind,
+        limits: SpaceLimits,
+    ) {
+        if let Some(server) = self.server_list.get(server_name) {
+            let mut words = vec![
+                String::from(CREATE),
+                String::from(admin_attribute),
+                String::from(tuple_space_name),
+            ];
+            words.extend(
+                store
+                    .to_options()
+                    .into_iter()
+                    .chain(limits.to_options())
+                    .map(|option| format!("{}{}", OPTION_PREFIX, option)),
+            );
+            words.extend(attributes);
+            println!("{}", server.send_message(words.join(SPACE)));
+        }
+    }
+
+    /// Writes the tuples of a tuple space into a file of the `dumps` directory of the server.
+    pub fn export(
+        &self,
+        server_name: &str,
+        admin_attribute: &str,
+        tuple_space_name: &str,
+        file_name: &str,
+        format: DumpFormat,
+    ) {
+        let words = vec![EXPORT, admin_attribute, tuple_space_name, file_name];
+        self.send_dump_request(server_name, words, format, vec![]);
+    }
+
+    /// Writes the tuples of a file of the `dumps` directory of the server into a tuple space,
+    /// which is created with the attributes if it does not exist.
+    pub fn import(
+        &self,
+        server_name: &str,
+        admin_attribute: &str,
+        tuple_space_name: &str,
+        file_name: &str,
+        format: DumpFormat,
+        attributes: Vec<String>,
+    ) {
+        let words = vec![IMPORT, admin_attribute, tuple_space_name, file_name];
+        self.send_dump_request(server_name, words, format, attributes);
+    }
+
+    fn send_dump_request(
+        &self,
+        server_name: &str,
+        words: Vec<&str>,
+        format: DumpFormat,
+        attributes: Vec<String>,
+    ) {
+        if let Some(server) = self.server_list.get(server_name) {
+            let mut words: Vec<String> = words.into_iter().map(String::from).collect();
+            words.push(format!("{}{}={}", OPTION_PREFIX, FORMAT, format.name()));
+            words.extend(attributes);
+            println!("{}", server.send_message(words.join(SPACE)));
+        }
+    }
+
     pub fn in_instr(&mut self, list_tuple: Vec<Tuple>) -> Tuple {
         return self.manage_primitives(IN, list_tuple);
     }
@@ -82,6 +165,20 @@ impl Client {
         self.manage_primitives(OUT, list_tuple);
     }
 
+    /// Writes the tuples into the attached tuple space, from which the server removes them once
+    /// the time-to-live is over.
+    pub fn out_with_ttl(&mut self, list_tuple: Vec<Tuple>, ttl: Duration) {
+        let operation = format!("{} {}={}", OUT, TTL, ttl.as_secs_f64());
+        self.manage_primitives(&operation, list_tuple);
+    }
+
+    /// Writes the tuples into the attached tuple space, visible only to the clients holding one
+    /// of the readers.
+    pub fn out_with_acl(&mut self, list_tuple: Vec<Tuple>, readers: Vec<String>) {
+        let operation = format!("{} {}={}", OUT, ACL, readers.join(","));
+        self.manage_primitives(&operation, list_tuple);
+    }
+
     pub fn read(&mut self, list_tuple: Vec<Tuple>) -> Tuple {
         return self.manage_primitives(READ, list_tuple);
     }
@@ -153,55 +250,322 @@ impl Client {
         }
     }
 
-    pub fn manage_primitives(&mut self, operation: &str, list_tuple: Vec<Tuple>) -> Tuple {
-        let server_attached = self.server_attached.clone();
-        let server = self.server_list.remove(&*server_attached);
-        match server {
-            None => {}
+    /// Lists the tuple spaces of a server the attributes give access to, as `(name, tuples,
+    /// (actions))` tuples. Without attributes, those of the attachment are used.
+    pub fn list(&self, server_name: &String, attributes: Vec<String>) -> Tuple {
+        let request = attributes
+            .iter()
+            .fold(String::from(LIST), |request, attribute| {
+                request + SPACE + attribute
+            });
+        self.send_word_request(server_name, request)
+    }
+
+    /// Lists the tuple spaces of a namespace of a server, like `list`.
+    pub fn list_namespace(
+        &self,
+        server_name: &String,
+        namespace: &str,
+        attributes: Vec<String>,
+    ) -> Tuple {
+        let request = attributes.iter().fold(
+            format!("{} {}{}={}", LIST, OPTION_PREFIX, PREFIX, namespace),
+            |request, attribute| request + SPACE + attribute,
+        );
+        self.send_word_request(server_name, request)
+    }
+
+    /// Deletes every tuple space of a namespace, which needs the attribute allowed to delete each
+    /// of them.
+    pub fn delete_namespace(&self, server_name: &str, delete_attribute: &str, namespace: &str) {
+        if let Some(server) = self.server_list.get(server_name) {
+            let words = [DELETE_NAMESPACE, delete_attribute, namespace];
+            println!("{}", server.send_message(words.join(SPACE)));
+        }
+    }
+
+    /// Describes a tuple space of a server as a `(name, tuples, bytes, (actions))` tuple.
+    pub fn describe(
+        &self,
+        server_name: &String,
+        tuple_space_name: &str,
+        attributes: Vec<String>,
+    ) -> Tuple {
+        let request = attributes.iter().fold(
+            String::from(DESCRIBE) + SPACE + tuple_space_name,
+            |request, attribute| request + SPACE + attribute,
+        );
+        self.send_word_request(server_name, request)
+    }
+
+    /// Returns the metadata of a tuple space of a server as a `(name, owner, created, description,
+    /// (options))` tuple, see `SpaceMetadata::to_tuple`.
+    pub fn metadata(
+        &self,
+        server_name: &String,
+        tuple_space_name: &str,
+        attributes: Vec<String>,
+    ) -> Tuple {
+        let request = attributes.iter().fold(
+            String::from(METADATA) + SPACE + tuple_space_name,
+            |request, attribute| request + SPACE + attribute,
+        );
+        self.send_word_request(server_name, request)
+    }
+
+    /// Returns the records of the audit log of a server matching the filters, such as
+    /// `space=DATA`, `result=deny` or `limit=10`, as tuples described by `AuditRecord::to_tuple`.
+    /// Needs the creation attribute.
+    pub fn audit(
+        &self,
+        server_name: &String,
+        admin_attribute: &str,
+        filters: Vec<String>,
+    ) -> Tuple {
+        let request = filters.iter().fold(
+            String::from(AUDIT) + SPACE + admin_attribute,
+            |request, filter| request + SPACE + filter,
+        );
+        self.send_word_request(server_name, request)
+    }
+
+    /// Gives an action on a tuple space or namespace to an attribute, with options such as
+    /// `ttl=3600` for a grant lapsing after an hour or `delegable`. The grantor attribute is either
+    /// the creation attribute or holds a delegable grant covering the new one. Returns `false` if
+    /// the grant is refused.
+    pub fn grant(
+        &self,
+        server_name: &str,
+        grantor_attribute: &str,
+        tuple_space_name: &str,
+        action: &str,
+        attribute: &str,
+        options: Vec<String>,
+    ) -> bool {
+        match self.server_list.get(server_name) {
+            None => false,
             Some(server) => {
-                let mut tuple_list: String = String::new();
-                for tuple in list_tuple {
-                    let request: String = String::from("(");
-                    tuple_list += &*(Client::format_tuple(tuple, request) + ")");
-                }
-                let mut response =
-                    server.send_message(String::from(operation) + SPACE + &*tuple_list);
+                let mut words = vec![
+                    String::from(GRANT),
+                    String::from(grantor_attribute),
+                    String::from(tuple_space_name),
+                    String::from(action),
+                    String::from(attribute),
+                ];
+                words.extend(options);
+                let response = server.send_message(words.join(SPACE));
                 println!("{}", response);
-                let _ = &self.server_list.insert(server_attached, server);
-                if response.contains(&String::from(ERROR)) || response.contains(&String::from(OK)) {
-                    return Tuple::new(&[]);
-                }
-                Client::remove_whitespace(&mut response);
-                let tuple_list: Vec<Tuple> = Lexer::new(&response).collect();
-                if let Some(response) = tuple_list.first() {
-                    return response.clone();
-                }
+                response == OK
             }
         }
-        return Tuple::new(&[]);
     }
 
-    fn format_tuple(tuple: Tuple, mut request: String) -> String {
-        if !tuple.is_empty() {
-            request = match tuple.first() {
-                S(value) => request + "\"" + value + "\"",
-                E::T(tuple) => {
-                    "(".to_owned() + &Client::format_tuple(tuple.clone(), request.clone()) + ")"
-                }
-                E::I(rest) => request + &*rest.to_string(),
-                E::D(rest) => request + &*rest.to_string(),
-                E::Any => request + "_",
-                E::None => request,
-            };
-            if !tuple.rest().is_empty() {
-                request += ","
+    /// Sets the description of a tuple space, which needs the attribute allowed to delete it.
+    pub fn set_description(
+        &self,
+        server_name: &str,
+        delete_attribute: &str,
+        tuple_space_name: &str,
+        description: &str,
+    ) {
+        if let Some(server) = self.server_list.get(server_name) {
+            let words = [
+                SET_DESCRIPTION,
+                delete_attribute,
+                tuple_space_name,
+                description,
+            ];
+            println!("{}", server.send_message(words.join(SPACE)));
+        }
+    }
+
+    fn send_word_request(&self, server_name: &String, request: String) -> Tuple {
+        match self.server_list.get(server_name) {
+            None => Tuple::new(&[]),
+            Some(server) => {
+                Client::decode_response(server.encoding(), server.send_bytes(request.into_bytes()))
+            }
+        }
+    }
+
+    /// Authenticates with a credential registered on the server, whose attributes are then the
+    /// only ones the requests to this server may claim. Returns `false` if it is refused.
+    pub fn authenticate(&self, server_name: &String, token: &str) -> bool {
+        match self.server_list.get(server_name) {
+            None => false,
+            Some(server) => {
+                let response = server.send_message(String::from(AUTH) + SPACE + token);
+                println!("{}", response);
+                response == AUTHENTICATED
+            }
+        }
+    }
+
+    /// Selects the encoding of the tuples exchanged with a server.
+    pub fn set_encoding(&mut self, server_name: &String, encoding: Encoding) {
+        if let Some(server) = self.server_list.get_mut(server_name) {
+            let response = server.send_message(String::from(ENCODING) + SPACE + encoding.name());
+            println!("{}", response);
+            if response == ENCODING_UPDATED {
+                server.set_encoding(encoding);
+            }
+        }
+    }
+
+    /// Aggregates the tuples matching the template on the server, grouped by the values of the
+    /// `group` field if any.
+    pub fn aggregate(
+        &mut self,
+        aggregation: Aggregation,
+        group: Option<usize>,
+        template: Tuple,
+    ) -> Tuple {
+        let operation = String::from(AGGREGATE) + SPACE + &aggregation.to_parameters(group);
+        self.manage_primitives(&operation, vec![template])
+    }
+
+    /// Aggregates the tuples matching the template like `aggregate`, and takes them out of the
+    /// tuple space.
+    pub fn aggregate_in(
+        &mut self,
+        aggregation: Aggregation,
+        group: Option<usize>,
+        template: Tuple,
+    ) -> Tuple {
+        let operation = String::from(AGGREGATE_IN) + SPACE + &aggregation.to_parameters(group);
+        self.manage_primitives(&operation, vec![template])
+    }
+
+    /// Applies `in`, `read` and `out` operations on the attached tuple space, either all of them or
+    /// none. Returns the tuples read or taken.
+    pub fn transaction(&mut self, operations: Vec<(&str, Vec<Tuple>)>) -> Tuple {
+        self.send_operations(Some(TRANSACTION), operations)
+    }
+
+    /// Moves the tuples matching the template from a tuple space to another one, on the attached
+    /// server, with the attributes of the attachment. Returns the tuples moved.
+    pub fn move_tuples(&mut self, source: &str, destination: &str, template: Tuple) -> Tuple {
+        let operation = String::from(MOVE) + SPACE + source + SPACE + destination;
+        self.manage_primitives(&operation, vec![template])
+    }
+
+    /// Copies the tuples matching the template from a tuple space to another one, like
+    /// `move_tuples` but leaving them in the first one.
+    pub fn copy_tuples(&mut self, source: &str, destination: &str, template: Tuple) -> Tuple {
+        let operation = String::from(COPY) + SPACE + source + SPACE + destination;
+        self.manage_primitives(&operation, vec![template])
+    }
+
+    /// Returns who wrote the tuples of a tuple space matching the template and when, on the
+    /// attached server, as a `(tuple, (author), written, (readers))` tuple for each.
+    pub fn provenance(
+        &mut self,
+        admin_attribute: &str,
+        tuple_space_name: &str,
+        template: Tuple,
+    ) -> Tuple {
+        let operation =
+            String::from(PROVENANCE) + SPACE + admin_attribute + SPACE + tuple_space_name;
+        self.manage_primitives(&operation, vec![template])
+    }
+
+    /// Calls the callback with every tuple matching the template written into the attached tuple
+    /// space, from a thread of the client. Returns the id of the subscription, or `None` if the
+    /// server refused it: only TCP servers push notifications.
+    pub fn subscribe<F>(&mut self, template: Tuple, callback: F) -> Option<u64>
+    where
+        F: FnMut(Tuple) + Send + 'static,
+    {
+        let server = self.server_list.get_mut(&self.server_attached)?;
+        // The callback is registered first, as a notification may come before the response.
+        let id = server.add_callback(Box::new(callback));
+        let mut request = format!("{} {} ", SUBSCRIBE, id).into_bytes();
+        match server.encoding() {
+            Encoding::Binary => request.append(&mut encode_tuples(&[template])),
+            Encoding::Text => request.extend_from_slice(format_tuples(&[template]).as_bytes()),
+        }
+        let response = String::from_utf8_lossy(&server.send_bytes(request)).into_owned();
+        println!("{}", response);
+        if response == OK {
+            Some(id)
+        } else {
+            server.remove_callback(id);
+            None
+        }
+    }
+
+    /// Subscribes like `subscribe`, the matching tuples being sent to the returned receiver.
+    pub fn subscribe_stream(&mut self, template: Tuple) -> Option<(u64, Receiver<Tuple>)> {
+        let (sender, receiver) = mpsc::channel();
+        let id = self.subscribe(template, move |tuple| {
+            let _ = sender.send(tuple);
+        })?;
+        Some((id, receiver))
+    }
+
+    /// Cancels a subscription made on the attached server.
+    pub fn unsubscribe(&mut self, id: u64) {
+        if let Some(server) = self.server_list.get(&self.server_attached) {
+            println!(
+                "{}",
+                server.send_message(String::from(UNSUBSCRIBE) + SPACE + &id.to_string())
+            );
+            server.remove_callback(id);
+        }
+    }
+
+    pub fn manage_primitives(&mut self, operation: &str, list_tuple: Vec<Tuple>) -> Tuple {
+        self.send_operations(None, vec![(operation, list_tuple)])
+    }
+
+    fn send_operations(
+        &mut self,
+        command: Option<&str>,
+        operations: Vec<(&str, Vec<Tuple>)>,
+    ) -> Tuple {
+        let server = match self.server_list.get(&self.server_attached) {
+            None => return Tuple::new(&[]),
+            Some(server) => server,
+        };
+        let encoding = server.encoding();
+        let mut request = Vec::new();
+        if let Some(command) = command {
+            request.extend_from_slice(command.as_bytes());
+        }
+        for (index, (operation, list_tuple)) in operations.iter().enumerate() {
+            if index != 0 {
+                request.extend_from_slice(b" ;");
+            }
+            if !request.is_empty() {
+                request.extend_from_slice(SPACE.as_bytes());
+            }
+            request.extend_from_slice(operation.as_bytes());
+            request.extend_from_slice(SPACE.as_bytes());
+            match encoding {
+                Encoding::Binary => request.append(&mut encode_tuples(list_tuple)),
+                Encoding::Text => request.extend_from_slice(format_tuples(list_tuple).as_bytes()),
             }
-            request = Client::format_tuple(tuple.rest(), request.clone())
         }
-        return request;
+        Client::decode_response(encoding, server.send_bytes(request))
     }
 
-    fn remove_whitespace(s: &mut String) {
-        s.retain(|c| !c.is_whitespace());
+    fn decode_response(encoding: Encoding, response: Vec<u8>) -> Tuple {
+        if let Some(Ok(tuple)) = encoding.decode_response(&response) {
+            return tuple;
+        }
+        let response = String::from_utf8_lossy(&response);
+        println!("{}", response);
+        // Data responses are tuples, anything else is a message from the server.
+        if encoding == Encoding::Text && response.starts_with('(') {
+            match parse_tuples(&response) {
+                Ok(tuple_list) => {
+                    if let Some(response) = tuple_list.first() {
+                        return response.clone();
+                    }
+                }
+                Err(error) => eprintln!("{}", error),
+            }
+        }
+        Tuple::new(&[])
     }
 }
@@ -0,0 +1,278 @@
+//! Module Codec
+//!
+//! Encodings used to carry tuples over the wire.
+//!
+//! The text encoding is the grammar of the `lexing` module and is kept for its readability. Either
+//! way the messages are encrypted, and over TCP the server prefixes each one with its length as a
+//! big-endian `u32`.
+//!
+//! The binary encoding writes every element as a one byte type tag followed by its value:
+//!
+//! | Element  | Tag    | Value                                        |
+//! |----------|--------|----------------------------------------------|
+//! | `E::I`   | `0x01` | 4 bytes, big endian `i32`                    |
+//! | `E::D`   | `0x02` | 8 bytes, big endian IEEE 754 bits of the f64 |
+//! | `E::S`   | `0x03` | 4 bytes big endian length, then UTF-8 bytes  |
+//! | `E::T`   | `0x04` | 4 bytes big endian element count, then items |
+//! | `E::Any` | `0x05` | nothing                                      |
+//! | `E::None`| `0x06` | nothing                                      |
+//!
+//! A binary request payload is a sequence of encoded tuples and a binary data response is a
+//! single encoded tuple, so it always starts with `TAG_TUPLE`.
+
+use rustupolis::tuple::{Tuple, E};
+
+use crate::constant::{BINARY, MAX_NESTING_DEPTH, TEXT};
+use crate::lexing::{format_tuple, parse_leading_tuples, parse_tuples};
+
+pub const TAG_INTEGER: u8 = 0x01;
+pub const TAG_FLOAT: u8 = 0x02;
+pub const TAG_STRING: u8 = 0x03;
+pub const TAG_TUPLE: u8 = 0x04;
+pub const TAG_ANY: u8 = 0x05;
+pub const TAG_NONE: u8 = 0x06;
+
+/// Encoding of the tuples exchanged on a connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Text,
+    Binary,
+}
+
+impl Encoding {
+    /// Returns the encoding matching the name used by the `encoding` command.
+    pub fn from_name(name: &str) -> Option<Encoding> {
+        match name {
+            TEXT => Some(Encoding::Text),
+            BINARY => Some(Encoding::Binary),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Text => TEXT,
+            Encoding::Binary => BINARY,
+        }
+    }
+
+    /// Decodes the tuples given as parameters of a request.
+    pub fn decode_tuples(&self, payload: &[u8]) -> Result<Vec<Tuple>, String> {
+        match self {
+            Encoding::Text => match std::str::from_utf8(payload) {
+                Ok(text) => parse_tuples(text),
+                Err(error) => Err(error.to_string()),
+            },
+            Encoding::Binary => {
+                let mut decoder = Decoder::new(payload);
+                let mut tuples = Vec::new();
+                while !decoder.is_finished() {
+                    tuples.push(decoder.read_tuple()?);
+                }
+                Ok(tuples)
+            }
+        }
+    }
+
+    /// Decodes the tuples at the start of a payload, up to the first byte which does not start a
+    /// tuple. Returns the tuples and the number of bytes read.
+    pub fn decode_leading_tuples(&self, payload: &[u8]) -> Result<(Vec<Tuple>, usize), String> {
+        match self {
+            Encoding::Text => match std::str::from_utf8(payload) {
+                Ok(text) => parse_leading_tuples(text),
+                Err(error) => Err(error.to_string()),
+            },
+            Encoding::Binary => {
+                let mut decoder = Decoder::new(payload);
+                let mut tuples = Vec::new();
+                while decoder.buf.get(decoder.pos) == Some(&TAG_TUPLE) {
+                    tuples.push(decoder.read_tuple()?);
+                }
+                Ok((tuples, decoder.pos))
+            }
+        }
+    }
+
+    /// Encodes the tuples returned by a request. A single tuple is sent as is, several tuples
+    /// are wrapped into one tuple.
+    pub fn encode_response(&self, tuples: &[Tuple]) -> Vec<u8> {
+        match self {
+            Encoding::Text => format_tuple(&response_tuple(tuples)).into_bytes(),
+            Encoding::Binary => {
+                let mut buffer = Vec::new();
+                encode_tuple(&response_tuple(tuples), &mut buffer);
+                buffer
+            }
+        }
+    }
+
+    /// Decodes a binary data response. Returns `None` when the response is a text message.
+    pub fn decode_response(&self, response: &[u8]) -> Option<Result<Tuple, String>> {
+        match self {
+            Encoding::Binary if response.first() == Some(&TAG_TUPLE) => {
+                Some(decode_tuple(response))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn response_tuple(tuples: &[Tuple]) -> Tuple {
+    if tuples.len() == 1 {
+        tuples[0].clone()
+    } else {
+        Tuple::from_vec(tuples.iter().cloned().map(E::T).collect())
+    }
+}
+
+/// Encodes the tuples given as parameters of a binary request.
+pub fn encode_tuples(tuples: &[Tuple]) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    for tuple in tuples {
+        encode_tuple(tuple, &mut buffer);
+    }
+    buffer
+}
+
+/// Returns the elements of a tuple.
+pub(crate) fn tuple_elements(tuple: &Tuple) -> Vec<E> {
+    let mut elements = Vec::new();
+    let mut rest = tuple.clone();
+    while !rest.is_empty() {
+        elements.push(rest.first().clone());
+        rest = rest.rest();
+    }
+    elements
+}
+
+/// Appends the binary encoding of a tuple to the buffer.
+pub fn encode_tuple(tuple: &Tuple, buffer: &mut Vec<u8>) {
+    let elements = tuple_elements(tuple);
+    buffer.push(TAG_TUPLE);
+    buffer.extend_from_slice(&(elements.len() as u32).to_be_bytes());
+    for element in &elements {
+        encode_element(element, buffer);
+    }
+}
+
+fn encode_element(element: &E, buffer: &mut Vec<u8>) {
+    match element {
+        E::I(value) => {
+            buffer.push(TAG_INTEGER);
+            buffer.extend_from_slice(&value.to_be_bytes());
+        }
+        E::D(value) => {
+            buffer.push(TAG_FLOAT);
+            buffer.extend_from_slice(&value.to_bits().to_be_bytes());
+        }
+        E::S(value) => {
+            buffer.push(TAG_STRING);
+            buffer.extend_from_slice(&(value.len() as u32).to_be_bytes());
+            buffer.extend_from_slice(value.as_bytes());
+        }
+        E::T(tuple) => encode_tuple(tuple, buffer),
+        E::Any => buffer.push(TAG_ANY),
+        E::None => buffer.push(TAG_NONE),
+    }
+}
+
+/// Decodes a single binary encoded tuple.
+pub fn decode_tuple(buffer: &[u8]) -> Result<Tuple, String> {
+    let mut decoder = Decoder::new(buffer);
+    let tuple = decoder.read_tuple()?;
+    if decoder.is_finished() {
+        Ok(tuple)
+    } else {
+        Err(String::from("trailing bytes after the tuple"))
+    }
+}
+
+/// Decodes the elements recursively, so their nesting is limited to `MAX_NESTING_DEPTH` levels.
+struct Decoder<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(buf: &'a [u8]) -> Decoder<'a> {
+        Decoder {
+            buf,
+            pos: 0,
+            depth: 0,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.pos >= self.buf.len()
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.buf.len() - self.pos < len {
+            return Err(format!("truncated value at byte {}", self.pos));
+        }
+        let bytes = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, String> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_tuple(&mut self) -> Result<Tuple, String> {
+        match self.read_element()? {
+            E::T(tuple) => Ok(tuple),
+            element => Err(format!("expected a tuple, found {}", element)),
+        }
+    }
+
+    fn read_element(&mut self) -> Result<E, String> {
+        let tag = self.read_bytes(1)?[0];
+        match tag {
+            TAG_INTEGER => {
+                let bytes = self.read_bytes(4)?;
+                Ok(E::I(i32::from_be_bytes([
+                    bytes[0], bytes[1], bytes[2], bytes[3],
+                ])))
+            }
+            TAG_FLOAT => {
+                let mut bits = [0; 8];
+                bits.copy_from_slice(self.read_bytes(8)?);
+                Ok(E::D(f64::from_bits(u64::from_be_bytes(bits))))
+            }
+            TAG_STRING => {
+                let len = self.read_u32()? as usize;
+                match std::str::from_utf8(self.read_bytes(len)?) {
+                    Ok(value) => Ok(E::str(value)),
+                    Err(error) => Err(error.to_string()),
+                }
+            }
+            TAG_TUPLE => {
+                let len = self.read_u32()? as usize;
+                // Each element takes at least one byte, which bounds the allocation below.
+                if len > self.buf.len() - self.pos {
+                    return Err(format!("truncated tuple at byte {}", self.pos));
+                }
+                if self.depth == MAX_NESTING_DEPTH {
+                    return Err(format!(
+                        "tuples nested more than {} levels deep",
+                        MAX_NESTING_DEPTH
+                    ));
+                }
+                self.depth += 1;
+                let mut elements = Vec::with_capacity(len);
+                for _ in 0..len {
+                    elements.push(self.read_element()?);
+                }
+                self.depth -= 1;
+                Ok(E::T(Tuple::from_vec(elements)))
+            }
+            TAG_ANY => Ok(E::Any),
+            TAG_NONE => Ok(E::None),
+            tag => Err(format!("unknown type tag {:#04x}", tag)),
+        }
+    }
+}
@@ -2,12 +2,75 @@ pub const PERMISSION: &str = "permission";
 pub const SPACE: &str = " ";
 pub const CREATE: &str = "create";
 pub const DELETE: &str = "delete";
+pub const DELETE_NAMESPACE: &str = "delete_namespace";
 pub const OUT: &str = "out";
 pub const IN: &str = "in";
 pub const READ: &str = "read";
 pub const ATTACH: &str = "attach";
+pub const AUTH: &str = "auth";
+pub const ENCODING: &str = "encoding";
+pub const TRANSACTION: &str = "transaction";
+pub const AGGREGATE: &str = "aggregate";
+pub const AGGREGATE_IN: &str = "aggregate_in";
+pub const SUBSCRIBE: &str = "subscribe";
+pub const UNSUBSCRIBE: &str = "unsubscribe";
+pub const NOTIFY: &str = "notify";
+pub const MOVE: &str = "move";
+pub const COPY: &str = "copy";
+pub const LIST: &str = "list";
+pub const DESCRIBE: &str = "describe";
+pub const EXPORT: &str = "export";
+pub const IMPORT: &str = "import";
+pub const FORMAT: &str = "format";
+pub const METADATA: &str = "metadata";
+pub const SET_DESCRIPTION: &str = "set_description";
+pub const PROVENANCE: &str = "provenance";
+pub const AUDIT: &str = "audit";
+pub const GRANT: &str = "grant";
+pub const PREFIX: &str = "prefix";
+pub const OPTION_PREFIX: &str = "--";
+pub const TUPLE_SPACE: &str = "space";
+pub const COMMAND: &str = "command";
+pub const ATTRIBUTE: &str = "attribute";
+pub const PEER: &str = "peer";
+pub const RESULT: &str = "result";
+pub const SINCE: &str = "since";
+pub const LIMIT: &str = "limit";
+pub const COUNT: &str = "count";
+pub const SUM: &str = "sum";
+pub const MIN: &str = "min";
+pub const MAX: &str = "max";
+pub const MEAN: &str = "mean";
+pub const FIELD: &str = "field";
+pub const GROUP: &str = "group";
+pub const TTL: &str = "ttl";
+pub const ACL: &str = "acl";
+pub const DELEGABLE: &str = "delegable";
+pub const MAX_TUPLES: &str = "max_tuples";
+pub const MAX_BYTES: &str = "max_bytes";
+pub const POLICY: &str = "policy";
+pub const POLICY_TIMEOUT: &str = "timeout";
+pub const REJECT: &str = "reject";
+pub const DROP_OLDEST: &str = "drop_oldest";
+pub const BLOCK: &str = "block";
+pub const STORE: &str = "store";
+pub const KEY_FIELD: &str = "key";
+pub const CAPACITY: &str = "capacity";
+pub const MEMORY: &str = "memory";
+pub const INDEXED: &str = "indexed";
+pub const RING: &str = "ring";
+pub const DISK: &str = "disk";
+pub const DISK_STORE_DIRECTORY: &str = "tuple_spaces";
+pub const TEXT: &str = "text";
+pub const BINARY: &str = "binary";
+pub const JSON: &str = "json";
+pub const DUMP_DIRECTORY: &str = "dumps";
+pub const AUTHENTICATED: &str = "Authenticated";
+pub const INVALID_CREDENTIAL: &str = "ERROR - Invalid credential";
+pub const CREDENTIAL_EXPIRED: &str = "ERROR - The credential expired";
 pub const TUPLE_SPACE_ATTACHED: &str = "Tuple space attached";
 pub const TUPLE_SPACE_NOT_FOUND: &str = "ERROR - Tuple space not found";
+pub const INVALID_TUPLE_SPACE_NAME: &str = "ERROR - Invalid tuple space name";
 pub const TUPLE_SPACE_ATTACHED_UPDATED: &str = "Tuple space attach updated";
 pub const NO_TUPLE_SPACE_ATTACHED: &str = "ERROR - No tuple space attached";
 pub const OK: &str = "Successful request";
@@ -16,9 +79,31 @@ pub const NO_MATCHING_TUPLE_FOUND: &str = "ERROR - No matching tuple could be fo
 pub const TUPLE_IS_EMPTY: &str = "ERROR - The tuple is empty";
 pub const REQUEST_DOESNT_EXIST: &str = "ERROR - The request doesn't exist";
 pub const EMPTY_REQUEST: &str = "ERROR - The request is empty";
+pub const ENCODING_UPDATED: &str = "Encoding updated";
+pub const UNKNOWN_ENCODING: &str = "ERROR - Unknown encoding";
+pub const INVALID_REQUEST: &str = "ERROR - The request is malformed";
+pub const INVALID_OPTION: &str = "ERROR - Invalid option";
+pub const TUPLE_SPACE_FULL: &str = "ERROR - The tuple space is full";
+pub const TUPLE_PENDING: &str = "The tuple space is full, the tuple waits for room";
+pub const UNKNOWN_AGGREGATION: &str = "ERROR - Unknown aggregation";
+pub const STORE_UNAVAILABLE: &str = "ERROR - The store cannot be opened";
+pub const ACL_UNSUPPORTED: &str = "ERROR - Disk stores do not keep access control lists";
+pub const ACL_NOT_EXPORTED: &str = "ERROR - Dumps do not keep access control lists";
+pub const AUDIT_UNAVAILABLE: &str = "ERROR - The audit log is not enabled or cannot be read";
+pub const DUMP_FAILED: &str = "ERROR - The dump cannot be read or written";
+pub const SUBSCRIPTION_UNSUPPORTED: &str = "ERROR - Subscriptions need a TCP connection";
+pub const UNKNOWN_SUBSCRIPTION: &str = "ERROR - Unknown subscription";
 pub const CONNECTED: &str = "Connected";
+pub const UNREADABLE_RESPONSE: &str = "ERROR - The response cannot be read";
 pub const STOP_SERVER: &str = "STOP";
 pub const TIMEOUT: u64 = 1;
+pub const EXPIRATION_SWEEP_INTERVAL: u64 = 100;
+pub const DEFAULT_BLOCK_TIMEOUT: u64 = 5;
+pub const MAX_PARKED_TUPLES: usize = 1024;
+pub const MAX_REACTION_DEPTH: usize = 8;
+pub const MAX_NESTING_DEPTH: usize = 64;
 pub const TCP: &str = "tcp";
 pub const UDP: &str = "udp";
-pub const ERROR: &str = "ERROR";
+pub const UDP_SESSION_IDLE_TIMEOUT: u64 = 300;
+pub const MAX_UDP_SESSIONS: usize = 1024;
+pub const MAX_UDP_PAYLOAD: usize = 65507;
@@ -0,0 +1,135 @@
+//! Module Credential
+//!
+//! Attribute credentials signed by an administrator with an Ed25519 key. A server trusting the
+//! public key of the issuer (see `Repository::add_issuer`) gives the attributes of a credential to
+//! the clients presenting it with `auth`, until it expires, so that fog nodes sharing the issuer do
+//! not need to know the credentials in advance.
+//!
+//! A credential is presented as a token holding no whitespace: its content and its signature in
+//! hexadecimal, separated by a dot. The content is the expiration time, in seconds since the Unix
+//! epoch, followed by the attributes, one per line.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use ed25519_dalek::{Signature, Signer};
+pub use ed25519_dalek::{SigningKey, VerifyingKey};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Credential {
+    attributes: Vec<String>,
+    expiration: SystemTime,
+    signature: Signature,
+}
+
+impl Credential {
+    /// Signs a credential giving the attributes until the expiration time, truncated to the
+    /// second. The attributes cannot hold whitespace, like those of the requests.
+    pub fn issue(
+        key: &SigningKey,
+        attributes: Vec<String>,
+        expiration: SystemTime,
+    ) -> Result<Credential, String> {
+        if attributes
+            .iter()
+            .any(|attribute| attribute.is_empty() || attribute.contains(char::is_whitespace))
+        {
+            return Err(String::from("invalid attribute"));
+        }
+        let seconds = expiration
+            .duration_since(UNIX_EPOCH)
+            .map_err(|error| error.to_string())?
+            .as_secs();
+        let expiration = UNIX_EPOCH + Duration::from_secs(seconds);
+        let signature = key.sign(&Credential::content(&attributes, seconds));
+        Ok(Credential {
+            attributes,
+            expiration,
+            signature,
+        })
+    }
+
+    pub fn attributes(&self) -> &[String] {
+        &self.attributes
+    }
+
+    pub fn expiration(&self) -> SystemTime {
+        self.expiration
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expiration <= SystemTime::now()
+    }
+
+    /// Returns `true` if the credential was signed by the key, whether it expired or not. Weak
+    /// keys and malleable signatures are refused.
+    pub fn is_signed_by(&self, key: &VerifyingKey) -> bool {
+        let seconds = self
+            .expiration
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        key.verify_strict(
+            &Credential::content(&self.attributes, seconds),
+            &self.signature,
+        )
+        .is_ok()
+    }
+
+    pub fn to_token(&self) -> String {
+        let seconds = self
+            .expiration
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        format!(
+            "{}.{}",
+            to_hex(&Credential::content(&self.attributes, seconds)),
+            to_hex(&self.signature.to_bytes())
+        )
+    }
+
+    /// Reads a token written by `to_token`, without checking its signature.
+    pub fn from_token(token: &str) -> Result<Credential, String> {
+        let (content, signature) = token
+            .split_once('.')
+            .ok_or_else(|| String::from("missing signature"))?;
+        let content = String::from_utf8(from_hex(content)?).map_err(|error| error.to_string())?;
+        let signature: [u8; 64] = from_hex(signature)?
+            .try_into()
+            .map_err(|_| String::from("invalid signature"))?;
+        let mut lines = content.split('\n');
+        let seconds = lines
+            .next()
+            .and_then(|seconds| seconds.parse::<u64>().ok())
+            .ok_or_else(|| String::from("invalid expiration"))?;
+        Ok(Credential {
+            attributes: lines.map(String::from).collect(),
+            expiration: UNIX_EPOCH + Duration::from_secs(seconds),
+            signature: Signature::from_bytes(&signature),
+        })
+    }
+
+    fn content(attributes: &[String], seconds: u64) -> Vec<u8> {
+        let mut content = seconds.to_string();
+        for attribute in attributes {
+            content.push('\n');
+            content.push_str(attribute);
+        }
+        content.into_bytes()
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(text: &str) -> Result<Vec<u8>, String> {
+    if !text.len().is_multiple_of(2) || !text.is_ascii() {
+        return Err(String::from("invalid hexadecimal"));
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|index| {
+            u8::from_str_radix(&text[index..index + 2], 16)
+                .map_err(|_| String::from("invalid hexadecimal"))
+        })
+        .collect()
+}
@@ -0,0 +1,75 @@
+//! Module Dump
+//!
+//! Files holding the tuples of a tuple space, written by `Repository::export_tuple_space` and read
+//! back by `Repository::import_tuple_space`. A dump holds one tuple per line, either in the text
+//! grammar of the `lexing` module or as JSON (see the `json` module), both of which keep nested
+//! tuples and the type of every value.
+
+use std::fs;
+use std::path::Path;
+
+use rustupolis::tuple::Tuple;
+
+use crate::constant::{JSON, TEXT};
+use crate::json::{tuple_from_json_str, tuple_to_json_string};
+use crate::lexing::{format_tuple, parse_tuples};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DumpFormat {
+    #[default]
+    Text,
+    JsonLines,
+}
+
+impl DumpFormat {
+    pub fn from_name(name: &str) -> Option<DumpFormat> {
+        match name {
+            TEXT => Some(DumpFormat::Text),
+            JSON => Some(DumpFormat::JsonLines),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DumpFormat::Text => TEXT,
+            DumpFormat::JsonLines => JSON,
+        }
+    }
+
+    /// Writes the tuples into the file, replacing it.
+    pub fn write(&self, path: &Path, tuples: &[Tuple]) -> Result<(), String> {
+        let mut dump = String::new();
+        for tuple in tuples {
+            match self {
+                DumpFormat::Text => dump.push_str(&format_tuple(tuple)),
+                DumpFormat::JsonLines => dump.push_str(&tuple_to_json_string(tuple)),
+            }
+            dump.push('\n');
+        }
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(|error| error.to_string())?;
+        }
+        fs::write(path, dump).map_err(|error| error.to_string())
+    }
+
+    /// Reads the tuples of the file. Blank lines are ignored.
+    pub fn read(&self, path: &Path) -> Result<Vec<Tuple>, String> {
+        let dump = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        let mut tuples = Vec::new();
+        for (number, line) in dump.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let result = match self {
+                DumpFormat::Text => parse_tuples(line).and_then(|mut parsed| match parsed.len() {
+                    1 => Ok(parsed.remove(0)),
+                    _ => Err(String::from("expected one tuple")),
+                }),
+                DumpFormat::JsonLines => tuple_from_json_str(line),
+            };
+            tuples.push(result.map_err(|error| format!("line {}: {}", number + 1, error))?);
+        }
+        Ok(tuples)
+    }
+}
@@ -0,0 +1,100 @@
+//! Module Expiration
+//!
+//! Removal of the tuples whose time-to-live is over. The expirations are kept in a heap ordered
+//! by deadline and the servers sweep it from a background thread, so the TCP and UDP event loops
+//! only pay for pushing into the heap.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use rustupolis::tuple::Tuple;
+
+use crate::managed_space::ManagedSpace;
+
+struct Expiration {
+    deadline: Instant,
+    tuple_space_name: String,
+    tuple_space: Weak<Mutex<ManagedSpace>>,
+    tuple: Tuple,
+}
+
+impl PartialEq for Expiration {
+    fn eq(&self, other: &Expiration) -> bool {
+        self.deadline == other.deadline
+    }
+}
+
+impl Eq for Expiration {}
+
+impl PartialOrd for Expiration {
+    fn partial_cmp(&self, other: &Expiration) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Expiration {
+    fn cmp(&self, other: &Expiration) -> Ordering {
+        self.deadline.cmp(&other.deadline)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Expirations {
+    heap: Arc<Mutex<BinaryHeap<Reverse<Expiration>>>>,
+}
+
+impl Expirations {
+    /// Schedules the removal of a tuple from a tuple space once its time-to-live is over. Nothing
+    /// is scheduled if the tuple space no longer holds the tuple.
+    pub fn schedule(
+        &self,
+        tuple_space_name: &str,
+        tuple_space: &Arc<Mutex<ManagedSpace>>,
+        tuple: Tuple,
+        ttl: Duration,
+    ) {
+        let deadline = Instant::now() + ttl;
+        if tuple_space.lock().unwrap().set_deadline(&tuple, deadline) {
+            self.heap.lock().unwrap().push(Reverse(Expiration {
+                deadline,
+                tuple_space_name: String::from(tuple_space_name),
+                tuple_space: Arc::downgrade(tuple_space),
+                tuple,
+            }));
+        }
+    }
+
+    /// Removes the tuples whose deadline is over at the given instant. Returns the tuple spaces
+    /// some tuples expired from, which may have room for their parked tuples.
+    pub fn sweep(&self, now: Instant) -> Vec<(String, Arc<Mutex<ManagedSpace>>)> {
+        let mut expired = Vec::new();
+        {
+            let mut heap = self.heap.lock().unwrap();
+            while heap
+                .peek()
+                .is_some_and(|expiration| expiration.0.deadline <= now)
+            {
+                expired.push(heap.pop().unwrap().0);
+            }
+        }
+        let mut swept: Vec<(String, Arc<Mutex<ManagedSpace>>)> = Vec::new();
+        for expiration in expired {
+            // The tuple space may have been removed in the meantime, and the tuple taken or
+            // written again with another deadline.
+            if let Some(tuple_space) = expiration.tuple_space.upgrade() {
+                if tuple_space.lock().unwrap().expire(&expiration.tuple, now) {
+                    println!("tuple {} expired", expiration.tuple);
+                    if !swept
+                        .iter()
+                        .any(|(_, swept_space)| Arc::ptr_eq(swept_space, &tuple_space))
+                    {
+                        swept.push((expiration.tuple_space_name, tuple_space));
+                    }
+                }
+            }
+        }
+        swept
+    }
+}
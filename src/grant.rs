@@ -0,0 +1,186 @@
+//! Module Grant
+//!
+//! Permissions given for a limited time or which their holders may pass on. Each grant is held in
+//! the permission tuple space as a `(name, action, (attribute), id)` tuple, which the expiration
+//! sweeper removes once the grant lapses, like the tuples written with a time-to-live. The holders
+//! of a delegable grant may give the same action to other attributes, on the same tuple space or
+//! one below it and for no longer than their own grant.
+
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+use rustupolis::tuple::{Tuple, E};
+
+use crate::namespace;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grant {
+    id: u64,
+    tuple_space_name: String,
+    action: String,
+    attribute: String,
+    expiration: Option<SystemTime>,
+    delegable: bool,
+    parent: Option<u64>,
+}
+
+impl Grant {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The tuple space or namespace the action is allowed on.
+    pub fn tuple_space_name(&self) -> &str {
+        &self.tuple_space_name
+    }
+
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    pub fn attribute(&self) -> &str {
+        &self.attribute
+    }
+
+    /// When the grant lapses, if ever.
+    pub fn expiration(&self) -> Option<SystemTime> {
+        self.expiration
+    }
+
+    pub fn is_delegable(&self) -> bool {
+        self.delegable
+    }
+
+    /// The id of the grant it was delegated from, if any.
+    pub fn parent(&self) -> Option<u64> {
+        self.parent
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expiration
+            .is_some_and(|expiration| expiration <= SystemTime::now())
+    }
+
+    /// Returns the tuple of the permission tuple space giving the action to the attribute,
+    /// followed by the id of the grant, written as a string so that every id fits.
+    pub fn to_permission(&self) -> Tuple {
+        Tuple::new(&[
+            E::str(&self.tuple_space_name),
+            E::str(&self.action),
+            E::T(Tuple::new(&[E::str(&self.attribute)])),
+            E::S(self.id.to_string()),
+        ])
+    }
+
+    /// Returns `true` if the grant may be delegated as the action on the tuple space, lapsing at
+    /// the expiration: the grant must be delegable, on the same action, on the tuple space or a
+    /// namespace above it, and last at least as long.
+    fn covers(&self, tuple_space_name: &str, action: &str, expiration: Option<SystemTime>) -> bool {
+        self.delegable
+            && !self.is_expired()
+            && self.action == action
+            && namespace::is_in_namespace(tuple_space_name, &self.tuple_space_name)
+            && match (self.expiration, expiration) {
+                (None, _) => true,
+                (Some(_), None) => false,
+                (Some(own), Some(delegated)) => delegated <= own,
+            }
+    }
+}
+
+/// The grants of every tuple space.
+#[derive(Default)]
+pub struct Grants {
+    grants: RwLock<(u64, Vec<Grant>)>,
+}
+
+impl Grants {
+    /// Adds a grant, delegated from the parent grant if any, and forgets the lapsed ones.
+    pub fn add(
+        &self,
+        tuple_space_name: &str,
+        action: &str,
+        attribute: &str,
+        expiration: Option<SystemTime>,
+        delegable: bool,
+        parent: Option<u64>,
+    ) -> Grant {
+        let mut grants = self.grants.write().unwrap();
+        grants.1.retain(|grant| !grant.is_expired());
+        let grant = Grant {
+            id: grants.0,
+            tuple_space_name: String::from(tuple_space_name),
+            action: String::from(action),
+            attribute: String::from(attribute),
+            expiration,
+            delegable,
+            parent,
+        };
+        grants.0 += 1;
+        grants.1.push(grant.clone());
+        grant
+    }
+
+    /// Removes the grant and those delegated from it, directly or not. Returns the grants removed.
+    pub fn remove(&self, id: u64) -> Vec<Grant> {
+        let mut grants = self.grants.write().unwrap();
+        let mut removed: Vec<Grant> = Vec::new();
+        let mut pending = vec![id];
+        while let Some(id) = pending.pop() {
+            let (revoked, kept) = grants
+                .1
+                .drain(..)
+                .partition(|grant: &Grant| grant.id == id || grant.parent == Some(id));
+            grants.1 = kept;
+            for grant in revoked {
+                if grant.id != id {
+                    pending.push(grant.id);
+                }
+                removed.push(grant);
+            }
+        }
+        removed
+    }
+
+    /// Removes the grants of a deleted tuple space.
+    pub fn remove_tuple_space(&self, tuple_space_name: &str) {
+        self.grants
+            .write()
+            .unwrap()
+            .1
+            .retain(|grant| grant.tuple_space_name != tuple_space_name);
+    }
+
+    /// Returns the grants on the tuple space or namespace itself which did not lapse.
+    pub fn get(&self, tuple_space_name: &str) -> Vec<Grant> {
+        self.grants
+            .read()
+            .unwrap()
+            .1
+            .iter()
+            .filter(|grant| grant.tuple_space_name == tuple_space_name && !grant.is_expired())
+            .cloned()
+            .collect()
+    }
+
+    /// Returns a delegable grant held by one of the attributes which may be delegated as the
+    /// action on the tuple space, lapsing at the expiration, if any.
+    pub fn delegable(
+        &self,
+        attributes: &[String],
+        tuple_space_name: &str,
+        action: &str,
+        expiration: Option<SystemTime>,
+    ) -> Option<Grant> {
+        self.grants
+            .read()
+            .unwrap()
+            .1
+            .iter()
+            .find(|grant| {
+                attributes.contains(&grant.attribute)
+                    && grant.covers(tuple_space_name, action, expiration)
+            })
+            .cloned()
+    }
+}
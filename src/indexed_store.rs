@@ -0,0 +1,161 @@
+//! Module Indexed Store
+//!
+//! A store which groups its tuples by arity, then by the value of a key field, so that a template
+//! only gets compared with the tuples it can match instead of the whole tuple space.
+//!
+//! Only integers and strings are used as keys, because floats match with a tolerance and nested
+//! tuples can hold wildcards. Tuples whose key field is of another type are kept aside and are
+//! compared with every template of their arity.
+//!
+//! Unlike `SimpleStore`, a template only matches tuples of the same arity.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use rustupolis::tuple::{Tuple, E};
+
+use crate::codec::tuple_elements;
+use crate::store::TupleStore;
+
+#[derive(Default)]
+struct ArityIndex {
+    by_key: BTreeMap<E, BTreeSet<Tuple>>,
+    unkeyed: BTreeSet<Tuple>,
+}
+
+impl ArityIndex {
+    fn is_empty(&self) -> bool {
+        self.by_key.is_empty() && self.unkeyed.is_empty()
+    }
+
+    /// Returns the tuples a template can match, which are only the tuples sharing its key when
+    /// it has one.
+    fn candidates<'a>(&'a self, key: &Option<E>) -> Box<dyn Iterator<Item = &'a Tuple> + 'a> {
+        match key {
+            Some(key) => Box::new(self.by_key.get(key).into_iter().flatten()),
+            None => Box::new(self.by_key.values().flatten().chain(self.unkeyed.iter())),
+        }
+    }
+
+    /// Returns the first tuple matching the template.
+    fn find(&self, template: &Tuple, key: Option<E>) -> Option<&Tuple> {
+        match &key {
+            Some(key) if template.is_defined() => self.by_key.get(key)?.get(template),
+            _ => self.candidates(&key).find(|tuple| template.matches(tuple)),
+        }
+    }
+}
+
+pub struct IndexedStore {
+    key_field: usize,
+    arities: HashMap<usize, ArityIndex>,
+    len: usize,
+}
+
+impl Default for IndexedStore {
+    fn default() -> IndexedStore {
+        IndexedStore::new(0)
+    }
+}
+
+impl IndexedStore {
+    /// Creates a store indexed on the field at the given position.
+    pub fn new(key_field: usize) -> IndexedStore {
+        IndexedStore {
+            key_field,
+            arities: HashMap::new(),
+            len: 0,
+        }
+    }
+
+    pub fn key_field(&self) -> usize {
+        self.key_field
+    }
+
+    /// Returns the arity of the tuple and the value of its key field, if it can be used as a key.
+    fn index_of(&self, tuple: &Tuple) -> (usize, Option<E>) {
+        let elements = tuple_elements(tuple);
+        let key = match elements.get(self.key_field) {
+            Some(element @ (E::I(_) | E::S(_))) => Some(element.clone()),
+            _ => None,
+        };
+        (elements.len(), key)
+    }
+
+    fn remove(&mut self, tuple: &Tuple) {
+        let (arity, key) = self.index_of(tuple);
+        if let Some(index) = self.arities.get_mut(&arity) {
+            let removed = match key {
+                Some(key) => match index.by_key.get_mut(&key) {
+                    Some(tuples) => {
+                        let removed = tuples.remove(tuple);
+                        if tuples.is_empty() {
+                            index.by_key.remove(&key);
+                        }
+                        removed
+                    }
+                    None => false,
+                },
+                None => index.unkeyed.remove(tuple),
+            };
+            if removed {
+                self.len -= 1;
+            }
+            if index.is_empty() {
+                self.arities.remove(&arity);
+            }
+        }
+    }
+}
+
+impl TupleStore for IndexedStore {
+    fn out(&mut self, tuple: Tuple) -> Result<Option<Tuple>, String> {
+        if !tuple.is_defined() {
+            return Err(String::from("cannot write an undefined tuple"));
+        }
+        let (arity, key) = self.index_of(&tuple);
+        let index = self.arities.entry(arity).or_default();
+        let inserted = match key {
+            Some(key) => index.by_key.entry(key).or_default().insert(tuple),
+            None => index.unkeyed.insert(tuple),
+        };
+        if inserted {
+            self.len += 1;
+        }
+        Ok(None)
+    }
+
+    fn inp(&mut self, template: &Tuple) -> Option<Tuple> {
+        let tuple = self.rdp(template)?;
+        self.remove(&tuple);
+        Some(tuple)
+    }
+
+    fn rdp(&mut self, template: &Tuple) -> Option<Tuple> {
+        let (arity, key) = self.index_of(template);
+        self.arities.get(&arity)?.find(template, key).cloned()
+    }
+
+    fn rdp_all(&self, template: &Tuple) -> Vec<Tuple> {
+        let (arity, key) = self.index_of(template);
+        match self.arities.get(&arity) {
+            Some(index) => index
+                .candidates(&key)
+                .filter(|tuple| template.matches(tuple))
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn tuples(&self) -> Vec<Tuple> {
+        self.arities
+            .values()
+            .flat_map(|index| index.by_key.values().flatten().chain(index.unkeyed.iter()))
+            .cloned()
+            .collect()
+    }
+}
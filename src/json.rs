@@ -0,0 +1,102 @@
+//! Module Json
+//!
+//! JSON representation of tuples, used to exchange tuples with tools that do not speak the text
+//! grammar and to store them in files.
+//!
+//! A tuple is an array of tagged values, each value being an object with a single key naming
+//! its type:
+//!
+//! ```json
+//! [{"string": "temp"}, {"int": 21}, {"float": 20.5}, {"tuple": [{"any": null}]}]
+//! ```
+//!
+//! Floats that JSON cannot represent are written as the strings `"NaN"`, `"inf"` and `"-inf"`.
+
+use rustupolis::tuple::{Tuple, E};
+use serde_json::{Map, Number, Value};
+
+use crate::codec::tuple_elements;
+
+pub const INT: &str = "int";
+pub const FLOAT: &str = "float";
+pub const STRING: &str = "string";
+pub const TUPLE: &str = "tuple";
+pub const ANY: &str = "any";
+pub const NONE: &str = "none";
+
+/// Returns the JSON value representing the tuple.
+pub fn tuple_to_json(tuple: &Tuple) -> Value {
+    Value::Array(tuple_elements(tuple).iter().map(element_to_json).collect())
+}
+
+/// Serializes the tuple into a JSON string.
+pub fn tuple_to_json_string(tuple: &Tuple) -> String {
+    tuple_to_json(tuple).to_string()
+}
+
+/// Builds a tuple from its JSON value.
+pub fn tuple_from_json(value: &Value) -> Result<Tuple, String> {
+    match value {
+        Value::Array(elements) => Ok(Tuple::from_vec(
+            elements
+                .iter()
+                .map(element_from_json)
+                .collect::<Result<Vec<E>, String>>()?,
+        )),
+        value => Err(format!(
+            "expected an array of tagged values, found {}",
+            value
+        )),
+    }
+}
+
+/// Parses a tuple from a JSON string.
+pub fn tuple_from_json_str(text: &str) -> Result<Tuple, String> {
+    match serde_json::from_str(text) {
+        Ok(value) => tuple_from_json(&value),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+fn element_to_json(element: &E) -> Value {
+    let (tag, value) = match element {
+        E::I(value) => (INT, Value::from(*value)),
+        E::D(value) => match Number::from_f64(*value) {
+            Some(number) => (FLOAT, Value::Number(number)),
+            None => (FLOAT, Value::from(value.to_string())),
+        },
+        E::S(value) => (STRING, Value::from(value.as_str())),
+        E::T(tuple) => (TUPLE, tuple_to_json(tuple)),
+        E::Any => (ANY, Value::Null),
+        E::None => (NONE, Value::Null),
+    };
+    let mut object = Map::with_capacity(1);
+    object.insert(String::from(tag), value);
+    Value::Object(object)
+}
+
+fn element_from_json(value: &Value) -> Result<E, String> {
+    let (tag, value) = match value {
+        Value::Object(object) if object.len() == 1 => object.iter().next().unwrap(),
+        value => return Err(format!("expected a tagged value, found {}", value)),
+    };
+    match (tag.as_str(), value) {
+        (INT, Value::Number(number)) => match number.as_i64().map(i32::try_from) {
+            Some(Ok(value)) => Ok(E::I(value)),
+            _ => Err(format!("{} is not a 32 bits integer", number)),
+        },
+        (FLOAT, Value::Number(number)) => match number.as_f64() {
+            Some(value) => Ok(E::D(value)),
+            None => Err(format!("{} is not a float", number)),
+        },
+        (FLOAT, Value::String(value)) => match value.parse::<f64>() {
+            Ok(value) if !value.is_finite() => Ok(E::D(value)),
+            _ => Err(format!("{} is not a float", value)),
+        },
+        (STRING, Value::String(value)) => Ok(E::str(value.as_str())),
+        (TUPLE, value) => Ok(E::T(tuple_from_json(value)?)),
+        (ANY, Value::Null) => Ok(E::Any),
+        (NONE, Value::Null) => Ok(E::None),
+        (tag, value) => Err(format!("invalid value {} for the tag {}", value, tag)),
+    }
+}
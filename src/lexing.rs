@@ -1,186 +1,267 @@
 //! Module Lexing
 //!
-//! Parsing of strings into instances of Tuple
+//! Parsing of strings into instances of Tuple, and the canonical formatting of tuples into the
+//! same grammar. Every tuple sent as text over the wire goes through `format_tuple` and comes back
+//! through `parse_tuples`, so that `parse_tuples(&format_tuple(&tuple))` gives back the tuple.
+//!
+//! The grammar is the following:
+//!
+//! - integers: `21`, `-4`
+//! - floats: always written with a decimal point or an exponent, `20.0`, `1e-7`, `NaN`, `inf`
+//! - strings: surrounded by quote marks, `\"` and `\\` escape the quote mark and the backslash
+//! - tuples: elements separated by commas and surrounded by parentheses, `("temp",21)`
+//! - wildcards: `_`
+//! - none: `nil`
+//!
 //! Heavily inspired by https://users.rust-lang.org/t/an-suggestions-improvements-for-my-lexer/6081
 
-use rustupolis::tuple;
-use rustupolis::tuple::{E, Tuple};
-
-#[derive(Debug)]
-enum TokenType<'a> {
-    Integer,
-    Float,
-    String,
-    Tuple(Vec<Option<Token<'a>>>),
-    Wildcard,
-}
+use rustupolis::tuple::{Tuple, E};
 
-#[derive(Debug)]
-struct Token<'a> {
-    typ: TokenType<'a>,
-    val: &'a str,
-}
+use crate::codec::tuple_elements;
+use crate::constant::MAX_NESTING_DEPTH;
 
+/// Byte oriented lexer going once over its buffer. Positions are byte offsets, and since every
+/// delimiter of the grammar is an ASCII character, the slices taken between delimiters are always
+/// valid UTF-8 and are borrowed from the buffer until the elements are built. Tuples are parsed
+/// recursively, so their nesting is limited to `MAX_NESTING_DEPTH` levels.
 pub struct Lexer<'a> {
     buf: &'a str,
     pos: usize,
+    depth: usize,
 }
 
 impl<'a> Iterator for Lexer<'a> {
-    type Item = Tuple;
+    type Item = Result<Tuple, String>;
 
-    fn next(&mut self) -> Option<Tuple> {
-        let chars = self.buf.chars().collect::<Vec<char>>();
-
-        if self.pos >= chars.len() {
+    fn next(&mut self) -> Option<Result<Tuple, String>> {
+        self.skip_separators();
+        if self.pos >= self.buf.len() {
             return None;
         }
-        if let E::T(tuple) = Lexer::from_token(&self.match_next(&chars)) {
-            Some(tuple)
-        } else {
-            Some(tuple![])
+        match self.match_next() {
+            Ok(E::T(tuple)) => Some(Ok(tuple)),
+            Ok(element) => Some(Err(format!("expected a tuple, found {}", element))),
+            Err(error) => {
+                // Stop at the first error rather than trying to resynchronize.
+                self.pos = self.buf.len();
+                Some(Err(error))
+            }
         }
     }
 }
 
 impl<'a> Lexer<'a> {
-    pub fn new(buffer: &str) -> Lexer {
+    pub fn new(buffer: &str) -> Lexer<'_> {
         Lexer {
             buf: buffer,
             pos: 0,
+            depth: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.buf.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_separators(&mut self) {
+        while self
+            .peek()
+            .is_some_and(|byte| byte == b',' || byte.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_while(&mut self, predicate: impl Fn(u8) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(&predicate) {
+            self.pos += 1;
         }
+        &self.buf[start..self.pos]
     }
 
-    fn match_next(&mut self, chars: &[char]) -> Option<Token<'a>> {
-        match chars[self.pos] {
+    fn match_next(&mut self) -> Result<E, String> {
+        match self.peek() {
             // parse numbers, which can be either negative or positive
-            '-' | '0'..='9' => self.parse_number(&chars),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
             // parse strings that are started and terminated by quote marks
-            '\"' => self.parse_string(&chars),
+            Some(b'\"') => self.parse_string(),
             // use a special character for wildcards
-            '_' => self.parse_wildcard(),
-            // parse tuples which are surrounded by parentheses
-            '(' => self.parse_tuple(&chars),
-            ',' => {
-                self.pos += 1;
-                None
-            }
-            _ => {
-                println!("invalid symbol {}", chars[self.pos]);
+            Some(b'_') => {
                 self.pos += 1;
-                None
+                Ok(E::Any)
             }
+            // parse tuples which are surrounded by parentheses
+            Some(b'(') => self.parse_tuple(),
+            Some(b'a'..=b'z' | b'A'..=b'Z') => self.parse_word(false),
+            Some(_) => match self.buf[self.pos..].chars().next() {
+                Some(symbol) => Err(format!("invalid symbol {}", symbol)),
+                None => Err(String::from("invalid symbol")),
+            },
+            None => Err(String::from("unexpected end of input")),
         }
     }
 
-    fn parse_number(&mut self, chars: &[char]) -> Option<Token<'a>> {
+    fn parse_number(&mut self) -> Result<E, String> {
         let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+            if self.peek().is_some_and(|byte| byte.is_ascii_alphabetic()) {
+                return self.parse_word(true);
+            }
+        }
         let mut is_float = false;
-        while self.pos < chars.len() {
-            match chars[self.pos] {
-                '-' | '0'..='9' => self.pos += 1,
-                '.' => {
-                    if is_float {
-                        // TODO: throw error
-                        panic!("float number with double decimal points");
-                    } else {
-                        is_float = true;
-                        self.pos += 1
-                    }
+        while let Some(byte) = self.peek() {
+            match byte {
+                b'0'..=b'9' => self.pos += 1,
+                b'.' | b'e' | b'E' => {
+                    is_float = true;
+                    self.pos += 1
+                }
+                // signs are only part of a number right after an exponent
+                b'-' | b'+' if matches!(self.buf.as_bytes()[self.pos - 1], b'e' | b'E') => {
+                    self.pos += 1
                 }
                 _ => break,
             }
         }
 
-        let typ = if is_float {
-            TokenType::Float
+        let val = &self.buf[start..self.pos];
+        if is_float {
+            match val.parse::<f64>() {
+                Ok(value) => Ok(E::D(value)),
+                Err(_) => Err(format!("invalid float {}", val)),
+            }
         } else {
-            TokenType::Integer
-        };
-        Some(Token {
-            typ,
-            val: &self.buf[start..self.pos],
-        })
+            match val.parse::<i32>() {
+                Ok(value) => Ok(E::I(value)),
+                Err(_) => Err(format!("invalid integer {}", val)),
+            }
+        }
     }
 
-    fn parse_string(&mut self, chars: &[char]) -> Option<Token<'a>> {
-        let start = self.pos;
+    fn parse_word(&mut self, negative: bool) -> Result<E, String> {
+        match (self.skip_while(|byte| byte.is_ascii_alphabetic()), negative) {
+            ("nil", false) => Ok(E::None),
+            ("NaN", false) => Ok(E::D(f64::NAN)),
+            ("inf", false) => Ok(E::D(f64::INFINITY)),
+            ("inf", true) => Ok(E::D(f64::NEG_INFINITY)),
+            (word, _) => Err(format!("invalid word {}", word)),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<E, String> {
         self.pos += 1;
-        while chars[self.pos] != '\"' {
-            self.pos += 1;
+        let mut value = String::new();
+        loop {
+            value.push_str(self.skip_while(|byte| byte != b'\"' && byte != b'\\'));
+            match self.peek() {
+                Some(b'\"') => break,
+                // the escaped character is taken as is, even when it is not ASCII
+                Some(b'\\') => match self.buf[self.pos + 1..].chars().next() {
+                    Some(character) => {
+                        value.push(character);
+                        self.pos += 1 + character.len_utf8();
+                    }
+                    None => return Err(String::from("incomplete string")),
+                },
+                _ => return Err(String::from("incomplete string")),
+            }
+        }
+        self.pos += 1;
+        Ok(E::S(value))
+    }
 
-            if self.pos >= chars.len() {
-                eprintln!("error: incomplete string!");
-                return None;
+    fn parse_tuple(&mut self) -> Result<E, String> {
+        if self.depth == MAX_NESTING_DEPTH {
+            return Err(format!(
+                "tuples nested more than {} levels deep",
+                MAX_NESTING_DEPTH
+            ));
+        }
+        self.depth += 1;
+        self.pos += 1;
+        let mut tuple_items: Vec<E> = Vec::new();
+        loop {
+            self.skip_separators();
+            match self.peek() {
+                None => return Err(String::from("incomplete tuple")),
+                Some(b')') => break,
+                Some(_) => tuple_items.push(self.match_next()?),
             }
         }
         self.pos += 1;
-        let end = self.pos;
+        self.depth -= 1;
+        Ok(E::T(Tuple::from_vec(tuple_items)))
+    }
+}
 
-        // println!("found string from {} to {}", start, self.pos);
-        // panic!();
+/// Parses a list of tuples, which can be separated by commas or whitespaces.
+pub fn parse_tuples(text: &str) -> Result<Vec<Tuple>, String> {
+    Lexer::new(text).collect()
+}
 
-        Some(Token {
-            typ: TokenType::String,
-            val: &self.buf[start..end],
-        })
+/// Parses the tuples at the start of the text, up to the first character which does not start a
+/// tuple. Returns the tuples and the number of bytes read.
+pub fn parse_leading_tuples(text: &str) -> Result<(Vec<Tuple>, usize), String> {
+    let mut lexer = Lexer::new(text);
+    let mut tuples = Vec::new();
+    loop {
+        lexer.skip_separators();
+        if lexer.peek() != Some(b'(') {
+            return Ok((tuples, lexer.pos));
+        }
+        if let E::T(tuple) = lexer.parse_tuple()? {
+            tuples.push(tuple);
+        }
     }
+}
 
-    fn parse_wildcard(&mut self) -> Option<Token<'a>> {
-        let start = self.pos;
-        self.pos += 1;
-        Some(Token {
-            typ: TokenType::Wildcard,
-            val: &self.buf[start - 1..self.pos],
-        })
-    }
+/// Formats a tuple with the canonical text grammar.
+pub fn format_tuple(tuple: &Tuple) -> String {
+    let mut text = String::new();
+    write_tuple(tuple, &mut text);
+    text
+}
 
-    fn parse_tuple(&mut self, chars: &[char]) -> Option<Token<'a>> {
-        let start = self.pos;
-        self.pos += 1;
-        let mut tuple_items: Vec<Option<Token<'a>>> = Vec::new();
-        while chars[self.pos] != ')' {
-            if let Some(token_opt) = self.match_next(chars) {
-                tuple_items.push(Some(token_opt));
-            }
-            if self.pos >= chars.len() {
-                eprintln!("error: incomplete tuple");
-                return None;
-            }
-            // eprintln!("pointer: {}", self.pos);
+/// Formats a list of tuples with the canonical text grammar.
+pub fn format_tuples(tuples: &[Tuple]) -> String {
+    tuples
+        .iter()
+        .map(format_tuple)
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn write_tuple(tuple: &Tuple, text: &mut String) {
+    text.push('(');
+    for (index, element) in tuple_elements(tuple).iter().enumerate() {
+        if index != 0 {
+            text.push(',');
         }
-        self.pos += 1;
-        Some(Token {
-            typ: TokenType::Tuple(tuple_items),
-            val: &self.buf[start..self.pos],
-        })
+        write_element(element, text);
     }
+    text.push(')');
+}
 
-    fn from_token(token_opt: &Option<Token>) -> E {
-        match token_opt {
-            Some(Token {
-                     typ: TokenType::Integer,
-                     val,
-                 }) => E::I(val.parse::<i32>().unwrap()),
-            Some(Token {
-                     typ: TokenType::Float,
-                     val,
-                 }) => E::D(val.parse::<f64>().unwrap()),
-            Some(Token {
-                     typ: TokenType::String,
-                     val,
-                 }) => E::S((*val).to_string()),
-            Some(Token {
-                     typ: TokenType::Wildcard,
-                     val: _,
-                 }) => E::Any,
-            Some(Token {
-                     typ: TokenType::Tuple(tokenlist),
-                     val: _,
-                 }) => E::T(Tuple::from_vec(
-                tokenlist.iter().map(|t| Lexer::from_token(t)).collect(),
-            )),
-            None => E::None,
+fn write_element(element: &E, text: &mut String) {
+    match element {
+        E::I(value) => text.push_str(&value.to_string()),
+        // The debug representation always keeps a decimal point or an exponent, and is the
+        // shortest one that reads back to the same value.
+        E::D(value) => text.push_str(&format!("{:?}", value)),
+        E::S(value) => {
+            text.push('\"');
+            for character in value.chars() {
+                if character == '\"' || character == '\\' {
+                    text.push('\\');
+                }
+                text.push(character);
+            }
+            text.push('\"');
         }
+        E::T(tuple) => write_tuple(tuple, text),
+        E::Any => text.push('_'),
+        E::None => text.push_str("nil"),
     }
 }
@@ -3,12 +3,31 @@ extern crate core;
 pub use rustupolis::tuple;
 pub use rustupolis::tuple::E;
 mod tuple_space;
+pub mod managed_space;
+pub mod indexed_store;
+pub mod store;
+pub mod metadata;
+pub mod provenance;
+pub mod aggregation;
+pub mod reaction;
+pub mod policy;
+pub mod codec;
+pub mod json;
+pub mod dump;
+pub mod credential;
+pub mod audit;
+pub mod grant;
 mod constant;
-mod lexing;
+pub mod lexing;
 pub mod repository;
 pub mod server;
 pub mod server_launcher;
 pub mod client;
 mod tcp_server;
 mod udp_server;
-mod server_client;
\ No newline at end of file
+mod server_client;
+pub mod session;
+mod subscription;
+mod expiration;
+mod namespace;
+mod role;
\ No newline at end of file
@@ -0,0 +1,463 @@
+//! Module Managed Space
+//!
+//! A tuple space of the repository, which keeps track of the number of tuples and bytes it holds
+//! to enforce its capacity limits.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use rustupolis::tuple::Tuple;
+
+use crate::codec::encode_tuple;
+use crate::constant::{
+    BLOCK, DEFAULT_BLOCK_TIMEOUT, DROP_OLDEST, MAX_BYTES, MAX_PARKED_TUPLES, MAX_TUPLES, POLICY,
+    POLICY_TIMEOUT, REJECT,
+};
+use crate::provenance::Provenance;
+use crate::store::TupleStore;
+
+/// What happens when a tuple is written into a full tuple space.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// The tuple is rejected with `TUPLE_SPACE_FULL`.
+    #[default]
+    Reject,
+    /// The oldest tuples are removed to make room for the new one.
+    DropOldest,
+    /// The tuple waits for room, at most for the given duration, before being dropped.
+    Block(Duration),
+}
+
+impl OverflowPolicy {
+    /// Returns the policy matching the name used by the `create` command.
+    pub fn from_name(name: &str, timeout: Duration) -> Option<OverflowPolicy> {
+        match name {
+            REJECT => Some(OverflowPolicy::Reject),
+            DROP_OLDEST => Some(OverflowPolicy::DropOldest),
+            BLOCK => Some(OverflowPolicy::Block(timeout)),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OverflowPolicy::Reject => REJECT,
+            OverflowPolicy::DropOldest => DROP_OLDEST,
+            OverflowPolicy::Block(_) => BLOCK,
+        }
+    }
+}
+
+/// Capacity limits of a tuple space. The size of a tuple is the length of its binary encoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct SpaceLimits {
+    max_tuples: Option<usize>,
+    max_bytes: Option<usize>,
+    policy: OverflowPolicy,
+}
+
+impl SpaceLimits {
+    pub fn new(
+        max_tuples: Option<usize>,
+        max_bytes: Option<usize>,
+        policy: OverflowPolicy,
+    ) -> SpaceLimits {
+        SpaceLimits {
+            max_tuples,
+            max_bytes,
+            policy,
+        }
+    }
+
+    pub fn unlimited() -> SpaceLimits {
+        SpaceLimits::default()
+    }
+
+    /// Reads the limits from the `name=value` options of the `create` command.
+    pub fn from_options(options: &[&str]) -> Option<SpaceLimits> {
+        let mut limits = SpaceLimits::unlimited();
+        let mut policy = REJECT;
+        let mut timeout = Duration::from_secs(DEFAULT_BLOCK_TIMEOUT);
+        for option in options {
+            match option.split_once('=')? {
+                (MAX_TUPLES, value) => limits.max_tuples = Some(value.parse().ok()?),
+                (MAX_BYTES, value) => limits.max_bytes = Some(value.parse().ok()?),
+                (POLICY, value) => policy = value,
+                (POLICY_TIMEOUT, value) => {
+                    timeout = Duration::try_from_secs_f64(value.parse().ok()?).ok()?
+                }
+                _ => return None,
+            }
+        }
+        limits.policy = OverflowPolicy::from_name(policy, timeout)?;
+        Some(limits)
+    }
+
+    /// Writes the limits as options of the `create` command.
+    pub fn to_options(&self) -> Vec<String> {
+        let mut options = Vec::new();
+        if let Some(max_tuples) = self.max_tuples {
+            options.push(format!("{}={}", MAX_TUPLES, max_tuples));
+        }
+        if let Some(max_bytes) = self.max_bytes {
+            options.push(format!("{}={}", MAX_BYTES, max_bytes));
+        }
+        options.push(format!("{}={}", POLICY, self.policy.name()));
+        if let OverflowPolicy::Block(timeout) = self.policy {
+            options.push(format!("{}={}", POLICY_TIMEOUT, timeout.as_secs_f64()));
+        }
+        options
+    }
+
+    pub fn max_tuples(&self) -> Option<usize> {
+        self.max_tuples
+    }
+
+    pub fn max_bytes(&self) -> Option<usize> {
+        self.max_bytes
+    }
+
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    fn allows(&self, tuple_count: usize, byte_count: usize) -> bool {
+        self.max_tuples.is_none_or(|max| tuple_count <= max)
+            && self.max_bytes.is_none_or(|max| byte_count <= max)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OutError {
+    /// The tuple does not fit in the tuple space.
+    Full,
+    /// The tuple could not be written, for instance because it is ill-defined.
+    Rejected(String),
+    /// The tuple does not fit in the tuple space and waits for room, see `ManagedSpace::park`.
+    Parked,
+}
+
+/// A tuple which waits for room in a tuple space whose overflow policy blocks.
+struct Parked {
+    tuple: Tuple,
+    provenance: Option<Provenance>,
+    ttl: Option<Duration>,
+    deadline: Instant,
+}
+
+pub struct ManagedSpace {
+    store: Box<dyn TupleStore>,
+    limits: SpaceLimits,
+    tuple_count: usize,
+    byte_count: usize,
+    // Insertion order of the tuples, only kept for the drop oldest policy.
+    order: VecDeque<Tuple>,
+    // Provenance of the tuples written by clients, by encoded tuple.
+    provenance: HashMap<Vec<u8>, Provenance>,
+    // Deadline of the tuples written with a time-to-live, by encoded tuple.
+    deadlines: HashMap<Vec<u8>, Instant>,
+    // Number of the provenances with readers, the tuples are only filtered when there are some.
+    restricted: usize,
+    // Tuples waiting for room, the oldest first.
+    parked: VecDeque<Parked>,
+}
+
+impl ManagedSpace {
+    /// Wraps a store, which may already hold tuples when it is read from disk.
+    pub fn new(store: Box<dyn TupleStore>, limits: SpaceLimits) -> ManagedSpace {
+        let tuples = store.tuples();
+        ManagedSpace {
+            store,
+            limits,
+            tuple_count: tuples.len(),
+            byte_count: tuples.iter().map(ManagedSpace::tuple_size).sum(),
+            order: if limits.policy == OverflowPolicy::DropOldest {
+                VecDeque::from(tuples)
+            } else {
+                VecDeque::new()
+            },
+            provenance: HashMap::new(),
+            deadlines: HashMap::new(),
+            restricted: 0,
+            parked: VecDeque::new(),
+        }
+    }
+
+    pub fn limits(&self) -> &SpaceLimits {
+        &self.limits
+    }
+
+    pub fn len(&self) -> usize {
+        self.tuple_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tuple_count == 0
+    }
+
+    /// Returns the total size of the tuples in bytes.
+    pub fn size(&self) -> usize {
+        self.byte_count
+    }
+
+    fn tuple_size(tuple: &Tuple) -> usize {
+        ManagedSpace::key(tuple).len()
+    }
+
+    fn key(tuple: &Tuple) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        encode_tuple(tuple, &mut buffer);
+        buffer
+    }
+
+    /// Writes a tuple, applying the overflow policy if it does not fit, and returns the tuples
+    /// dropped to make room. A blocking policy is reported as `OutError::Full` and left to the
+    /// caller, who may `park` the tuple.
+    pub fn out(&mut self, tuple: Tuple) -> Result<Vec<Tuple>, OutError> {
+        let dropped = self.write(tuple, None)?;
+        Ok(dropped.into_iter().map(|(tuple, _)| tuple).collect())
+    }
+
+    /// Writes a tuple like `out` and records its provenance, unless the tuple space already holds
+    /// the tuple, which keeps the provenance of the first write.
+    pub fn out_with_provenance(
+        &mut self,
+        tuple: Tuple,
+        provenance: Provenance,
+    ) -> Result<Vec<Tuple>, OutError> {
+        let dropped = self.write(tuple, Some(provenance))?;
+        Ok(dropped.into_iter().map(|(tuple, _)| tuple).collect())
+    }
+
+    /// Writes a tuple like `out_with_provenance`, with a provenance if any, and returns the tuples
+    /// dropped to make room along with their provenance, so that they can be written back.
+    pub fn write(
+        &mut self,
+        tuple: Tuple,
+        provenance: Option<Provenance>,
+    ) -> Result<Vec<(Tuple, Option<Provenance>)>, OutError> {
+        let dropped = self.insert(tuple.clone())?;
+        if let Some(provenance) = provenance {
+            if let Entry::Vacant(entry) = self.provenance.entry(ManagedSpace::key(&tuple)) {
+                if provenance.readers().is_some() {
+                    self.restricted += 1;
+                }
+                entry.insert(provenance);
+            }
+        }
+        Ok(dropped)
+    }
+
+    /// Writes a tuple into the store, applying the overflow policy, and updates the counts.
+    fn insert(&mut self, tuple: Tuple) -> Result<Vec<(Tuple, Option<Provenance>)>, OutError> {
+        // The store is a set, writing a tuple it already holds does not take more room.
+        if self.read(tuple.clone()).is_some() {
+            return Ok(Vec::new());
+        }
+        let size = ManagedSpace::tuple_size(&tuple);
+        if !self.limits.allows(1, size) {
+            return Err(OutError::Full);
+        }
+        let mut dropped = Vec::new();
+        while !self
+            .limits
+            .allows(self.tuple_count + 1, self.byte_count + size)
+        {
+            match (self.limits.policy, self.order.front().cloned()) {
+                (OverflowPolicy::DropOldest, Some(oldest)) => {
+                    println!("dropping tuple {} to make room", oldest);
+                    let provenance = self.provenance(&oldest).cloned();
+                    self.remove(&oldest);
+                    dropped.push((oldest, provenance));
+                }
+                _ => return Err(OutError::Full),
+            }
+        }
+        match self.store.out(tuple.clone()) {
+            Ok(evicted) => {
+                self.tuple_count += 1;
+                self.byte_count += size;
+                if self.limits.policy == OverflowPolicy::DropOldest {
+                    self.order.push_back(tuple);
+                }
+                if let Some(evicted) = evicted {
+                    println!("dropping tuple {} to make room", evicted);
+                    let provenance = self.provenance(&evicted).cloned();
+                    self.forget(&evicted);
+                    dropped.push((evicted, provenance));
+                }
+                Ok(dropped)
+            }
+            Err(error) => Err(OutError::Rejected(error)),
+        }
+    }
+
+    /// Keeps a tuple which does not fit aside until `unpark` finds room for it, at most for the
+    /// timeout of the blocking policy. Returns `false` if the policy does not block or if
+    /// `MAX_PARKED_TUPLES` tuples already wait.
+    pub fn park(
+        &mut self,
+        tuple: Tuple,
+        provenance: Option<Provenance>,
+        ttl: Option<Duration>,
+    ) -> bool {
+        let timeout = match self.limits.policy {
+            OverflowPolicy::Block(timeout) => timeout,
+            _ => return false,
+        };
+        let now = Instant::now();
+        self.parked.retain(|parked| parked.deadline > now);
+        if self.parked.len() >= MAX_PARKED_TUPLES {
+            return false;
+        }
+        self.parked.push_back(Parked {
+            tuple,
+            provenance,
+            ttl,
+            deadline: now + timeout,
+        });
+        true
+    }
+
+    /// Writes the parked tuples which now fit, the oldest first, and drops those which waited
+    /// longer than the timeout. Returns the tuples written, with their provenance and
+    /// time-to-live.
+    pub fn unpark(&mut self) -> Vec<(Tuple, Option<Provenance>, Option<Duration>)> {
+        let mut written = Vec::new();
+        while let Some(parked) = self.parked.pop_front() {
+            if parked.deadline <= Instant::now() {
+                println!("dropping tuple {} which waited too long", parked.tuple);
+                continue;
+            }
+            let result = match &parked.provenance {
+                Some(provenance) => {
+                    self.out_with_provenance(parked.tuple.clone(), provenance.clone())
+                }
+                None => self.out(parked.tuple.clone()),
+            };
+            match result {
+                Ok(_) => written.push((parked.tuple, parked.provenance, parked.ttl)),
+                Err(OutError::Full) => {
+                    self.parked.push_front(parked);
+                    break;
+                }
+                Err(error) => eprintln!("Cannot write tuple {}: {:?}", parked.tuple, error),
+            }
+        }
+        written
+    }
+
+    /// Returns the provenance of a tuple written with `out_with_provenance`.
+    pub fn provenance(&self, tuple: &Tuple) -> Option<&Provenance> {
+        self.provenance.get(&ManagedSpace::key(tuple))
+    }
+
+    /// Returns when the tuple expires, if it was written with a time-to-live.
+    pub fn deadline(&self, tuple: &Tuple) -> Option<Instant> {
+        self.deadlines.get(&ManagedSpace::key(tuple)).copied()
+    }
+
+    /// Sets when the tuple expires, unless it already expires earlier. Returns `false` if the
+    /// tuple space does not hold the tuple.
+    pub fn set_deadline(&mut self, tuple: &Tuple, deadline: Instant) -> bool {
+        if !self.store.rdp_all(tuple).contains(tuple) {
+            return false;
+        }
+        let current = self
+            .deadlines
+            .entry(ManagedSpace::key(tuple))
+            .or_insert(deadline);
+        *current = deadline.min(*current);
+        true
+    }
+
+    /// Removes the tuple if its deadline is over at the given instant. A tuple taken then written
+    /// again only expires with its new deadline, if any. Returns `true` if the tuple was removed.
+    pub fn expire(&mut self, tuple: &Tuple, now: Instant) -> bool {
+        match self.deadline(tuple) {
+            Some(deadline) if deadline <= now => self.remove(tuple),
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if a client holding the attributes may see the tuple.
+    pub fn is_visible(&self, tuple: &Tuple, attributes: &[String]) -> bool {
+        self.provenance(tuple)
+            .is_none_or(|provenance| provenance.allows(attributes))
+    }
+
+    /// Returns `true` if some tuples were written with an access control list.
+    pub fn has_readers(&self) -> bool {
+        self.restricted > 0
+    }
+
+    /// Returns a tuple matching the template which the attributes may see, if any.
+    pub fn read_visible(&mut self, template: Tuple, attributes: &[String]) -> Option<Tuple> {
+        if !self.has_readers() {
+            return self.read(template);
+        }
+        self.read_all_visible(&template, attributes)
+            .into_iter()
+            .next()
+    }
+
+    /// Returns every tuple matching the template which the attributes may see.
+    pub fn read_all_visible(&self, template: &Tuple, attributes: &[String]) -> Vec<Tuple> {
+        let mut tuples = self.read_all(template);
+        tuples.retain(|tuple| self.is_visible(tuple, attributes));
+        tuples
+    }
+
+    /// Removes and returns a tuple matching the template which the attributes may see, if any.
+    pub fn take_visible(&mut self, template: Tuple, attributes: &[String]) -> Option<Tuple> {
+        if !self.has_readers() {
+            return self.take(template);
+        }
+        let tuple = self.read_visible(template, attributes)?;
+        self.take(tuple)
+    }
+
+    /// Returns a tuple matching the template, if any.
+    pub fn read(&mut self, template: Tuple) -> Option<Tuple> {
+        self.store.rdp(&template)
+    }
+
+    /// Returns every tuple matching the template.
+    pub fn read_all(&self, template: &Tuple) -> Vec<Tuple> {
+        self.store.rdp_all(template)
+    }
+
+    /// Removes and returns a tuple matching the template, if any.
+    pub fn take(&mut self, template: Tuple) -> Option<Tuple> {
+        let tuple = self.store.inp(&template)?;
+        self.forget(&tuple);
+        Some(tuple)
+    }
+
+    /// Returns a copy of every tuple of the tuple space.
+    pub fn tuples(&self) -> Vec<Tuple> {
+        self.store.tuples()
+    }
+
+    /// Updates the counts once a tuple has left the store.
+    fn forget(&mut self, tuple: &Tuple) {
+        let key = ManagedSpace::key(tuple);
+        self.tuple_count -= 1;
+        self.byte_count -= key.len();
+        if let Some(provenance) = self.provenance.remove(&key) {
+            if provenance.readers().is_some() {
+                self.restricted -= 1;
+            }
+        }
+        self.deadlines.remove(&key);
+        if let Some(index) = self.order.iter().position(|other| other == tuple) {
+            self.order.remove(index);
+        }
+    }
+
+    /// Removes the given tuple. Returns `false` if the tuple space does not hold it.
+    pub fn remove(&mut self, tuple: &Tuple) -> bool {
+        self.take(tuple.clone()).is_some()
+    }
+}
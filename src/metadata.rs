@@ -0,0 +1,93 @@
+//! Module Metadata
+//!
+//! What the repository records about each tuple space besides its tuples, so that operators can
+//! tell what a tuple space is for and how it was created.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rustupolis::tuple::{Tuple, E};
+
+use crate::managed_space::SpaceLimits;
+use crate::store::StoreKind;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpaceMetadata {
+    owner: Option<String>,
+    created: SystemTime,
+    description: String,
+    store: StoreKind,
+    limits: SpaceLimits,
+}
+
+impl SpaceMetadata {
+    /// Creates the metadata of a tuple space created now, by the owner of the given attribute if
+    /// any.
+    pub fn new(
+        owner: Option<String>,
+        description: String,
+        store: StoreKind,
+        limits: SpaceLimits,
+    ) -> SpaceMetadata {
+        SpaceMetadata {
+            owner,
+            created: SystemTime::now(),
+            description,
+            store,
+            limits,
+        }
+    }
+
+    /// The attribute of the creator, for tuple spaces created through the `create` or `import`
+    /// commands.
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    pub fn created(&self) -> SystemTime {
+        self.created
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn set_description(&mut self, description: String) {
+        self.description = description;
+    }
+
+    pub fn store(&self) -> &StoreKind {
+        &self.store
+    }
+
+    pub fn limits(&self) -> SpaceLimits {
+        self.limits
+    }
+
+    /// Returns the metadata as the response of the `metadata` command: `(name, owner, created,
+    /// description, (options))`, where `owner` is `nil` if unknown, `created` is the number of
+    /// seconds since the Unix epoch and `options` are the options of the `create` command.
+    pub fn to_tuple(&self, tuple_space_name: &str) -> Tuple {
+        let owner = match &self.owner {
+            Some(owner) => E::str(owner),
+            None => E::None,
+        };
+        let created = self
+            .created
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |duration| duration.as_secs_f64());
+        let options = self
+            .store
+            .to_options()
+            .into_iter()
+            .chain(self.limits.to_options())
+            .map(E::str)
+            .collect();
+        Tuple::new(&[
+            E::str(tuple_space_name),
+            owner,
+            E::D(created),
+            E::str(&self.description),
+            E::T(Tuple::from_vec(options)),
+        ])
+    }
+}
@@ -0,0 +1,28 @@
+//! Module Namespace
+//!
+//! Tuple space names are paths such as `siteA/floor2/temp`, each prefix being a namespace. The
+//! permissions given on a namespace apply to every tuple space below it.
+
+pub const SEPARATOR: char = '/';
+
+/// Returns `true` if the name is made of non-empty segments separated by `SEPARATOR`.
+pub fn is_valid_name(name: &str) -> bool {
+    name.split(SEPARATOR).all(|segment| !segment.is_empty())
+}
+
+/// Returns the name followed by the namespaces it belongs to, from the nearest one:
+/// `a/b/c`, `a/b`, then `a`.
+pub fn ancestors(name: &str) -> impl Iterator<Item = &str> {
+    let mut next = Some(name);
+    std::iter::from_fn(move || {
+        let current = next?;
+        next = current.rsplit_once(SEPARATOR).map(|(parent, _)| parent);
+        Some(current)
+    })
+}
+
+/// Returns `true` if the name is the namespace itself or a name below it.
+pub fn is_in_namespace(name: &str, namespace: &str) -> bool {
+    name.strip_prefix(namespace)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(SEPARATOR))
+}
@@ -0,0 +1,248 @@
+//! Module Policy
+//!
+//! Boolean expressions over attributes allowing or denying an action on a tuple space, such as
+//! `siteA and operator` or `staff and not (contractor or intern)`. `not` binds tighter than `and`,
+//! which binds tighter than `or`. A policy given on a namespace applies to every tuple space below
+//! it, and a policy denying an action takes precedence over every permission allowing it.
+
+use std::fmt;
+use std::sync::RwLock;
+
+use crate::namespace;
+
+const AND: &str = "and";
+const OR: &str = "or";
+const NOT: &str = "not";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Policy {
+    Attribute(String),
+    Not(Box<Policy>),
+    And(Box<Policy>, Box<Policy>),
+    Or(Box<Policy>, Box<Policy>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+    Allow,
+    Deny,
+}
+
+impl Policy {
+    pub fn parse(expression: &str) -> Result<Policy, String> {
+        let spaced = expression.replace('(', " ( ").replace(')', " ) ");
+        let tokens: Vec<&str> = spaced.split_whitespace().collect();
+        let mut position = 0;
+        let policy = Policy::parse_or(&tokens, &mut position)?;
+        match tokens.get(position) {
+            None => Ok(policy),
+            Some(token) => Err(format!("unexpected {}", token)),
+        }
+    }
+
+    fn parse_or(tokens: &[&str], position: &mut usize) -> Result<Policy, String> {
+        let mut policy = Policy::parse_and(tokens, position)?;
+        while tokens.get(*position) == Some(&OR) {
+            *position += 1;
+            policy = Policy::Or(
+                Box::new(policy),
+                Box::new(Policy::parse_and(tokens, position)?),
+            );
+        }
+        Ok(policy)
+    }
+
+    fn parse_and(tokens: &[&str], position: &mut usize) -> Result<Policy, String> {
+        let mut policy = Policy::parse_not(tokens, position)?;
+        while tokens.get(*position) == Some(&AND) {
+            *position += 1;
+            policy = Policy::And(
+                Box::new(policy),
+                Box::new(Policy::parse_not(tokens, position)?),
+            );
+        }
+        Ok(policy)
+    }
+
+    fn parse_not(tokens: &[&str], position: &mut usize) -> Result<Policy, String> {
+        let token = tokens
+            .get(*position)
+            .ok_or_else(|| String::from("unexpected end of the expression"))?;
+        *position += 1;
+        match *token {
+            NOT => Ok(Policy::Not(Box::new(Policy::parse_not(tokens, position)?))),
+            "(" => {
+                let policy = Policy::parse_or(tokens, position)?;
+                match tokens.get(*position) {
+                    Some(&")") => {
+                        *position += 1;
+                        Ok(policy)
+                    }
+                    _ => Err(String::from("missing )")),
+                }
+            }
+            AND | OR | ")" => Err(format!("unexpected {}", token)),
+            attribute => Ok(Policy::Attribute(String::from(attribute))),
+        }
+    }
+
+    /// Returns `true` if the attributes satisfy the policy.
+    pub fn evaluate(&self, attributes: &[String]) -> bool {
+        self.evaluate_claimed(attributes, attributes)
+    }
+
+    /// Returns `true` if the attributes claimed satisfy the policy, the `not` terms being
+    /// evaluated against every attribute held, so that claiming fewer attributes does not satisfy
+    /// them.
+    pub fn evaluate_claimed(&self, claimed: &[String], held: &[String]) -> bool {
+        match self {
+            Policy::Attribute(attribute) => claimed.contains(attribute),
+            Policy::Not(policy) => !policy.evaluate(held),
+            Policy::And(left, right) => {
+                left.evaluate_claimed(claimed, held) && right.evaluate_claimed(claimed, held)
+            }
+            Policy::Or(left, right) => {
+                left.evaluate_claimed(claimed, held) || right.evaluate_claimed(claimed, held)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Policy {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        // Writes the parentheses only where the precedence needs them.
+        let operand = |policy: &Policy, grouped: bool| match grouped {
+            true => format!("({})", policy),
+            false => policy.to_string(),
+        };
+        match self {
+            Policy::Attribute(attribute) => write!(formatter, "{}", attribute),
+            Policy::Not(policy) => {
+                let grouped = matches!(**policy, Policy::And(..) | Policy::Or(..));
+                write!(formatter, "{} {}", NOT, operand(policy, grouped))
+            }
+            Policy::And(left, right) => write!(
+                formatter,
+                "{} {} {}",
+                operand(left, matches!(**left, Policy::Or(..))),
+                AND,
+                operand(
+                    right,
+                    !matches!(**right, Policy::Attribute(_) | Policy::Not(_))
+                )
+            ),
+            Policy::Or(left, right) => write!(
+                formatter,
+                "{} {} {}",
+                left,
+                OR,
+                operand(right, matches!(**right, Policy::Or(..)))
+            ),
+        }
+    }
+}
+
+struct Rule {
+    id: u64,
+    tuple_space_name: Option<String>,
+    action: String,
+    effect: Effect,
+    policy: Policy,
+}
+
+/// The policies of every tuple space and action.
+#[derive(Default)]
+pub struct Policies {
+    rules: RwLock<(u64, Vec<Rule>)>,
+}
+
+impl Policies {
+    /// Adds a policy on the action, on the tuple space or namespace if any. Returns the id of the
+    /// policy.
+    pub fn add(
+        &self,
+        tuple_space_name: Option<&str>,
+        action: &str,
+        effect: Effect,
+        policy: Policy,
+    ) -> u64 {
+        let mut rules = self.rules.write().unwrap();
+        let id = rules.0;
+        rules.0 += 1;
+        rules.1.push(Rule {
+            id,
+            tuple_space_name: tuple_space_name.map(String::from),
+            action: String::from(action),
+            effect,
+            policy,
+        });
+        id
+    }
+
+    /// Returns `false` if there is no policy with this id.
+    pub fn remove(&self, id: u64) -> bool {
+        let mut rules = self.rules.write().unwrap();
+        let count = rules.1.len();
+        rules.1.retain(|rule| rule.id != id);
+        rules.1.len() != count
+    }
+
+    /// Removes the policies of a deleted tuple space.
+    pub fn remove_tuple_space(&self, tuple_space_name: &str) {
+        self.rules
+            .write()
+            .unwrap()
+            .1
+            .retain(|rule| rule.tuple_space_name.as_deref() != Some(tuple_space_name));
+    }
+
+    /// Returns the policies on the tuple space itself, or on no tuple space, with their id.
+    pub fn get(&self, tuple_space_name: Option<&str>) -> Vec<(u64, String, Effect, Policy)> {
+        self.rules
+            .read()
+            .unwrap()
+            .1
+            .iter()
+            .filter(|rule| rule.tuple_space_name.as_deref() == tuple_space_name)
+            .map(|rule| {
+                (
+                    rule.id,
+                    rule.action.clone(),
+                    rule.effect,
+                    rule.policy.clone(),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns `Deny` if a policy satisfied by the attributes held denies the action on the tuple
+    /// space or one of its namespaces, `Allow` if one satisfied by the attributes claimed allows
+    /// it, and `None` if no policy applies.
+    pub fn decide(
+        &self,
+        action: &str,
+        tuple_space_name: Option<&str>,
+        claimed: &[String],
+        held: &[String],
+    ) -> Option<Effect> {
+        let mut decision = None;
+        for rule in self.rules.read().unwrap().1.iter() {
+            let applies = match (&rule.tuple_space_name, tuple_space_name) {
+                (None, None) => true,
+                (Some(namespace), Some(name)) => namespace::is_in_namespace(name, namespace),
+                _ => false,
+            };
+            if rule.action != action || !applies {
+                continue;
+            }
+            match rule.effect {
+                Effect::Deny if rule.policy.evaluate(held) => return Some(Effect::Deny),
+                Effect::Allow if rule.policy.evaluate_claimed(claimed, held) => {
+                    decision = Some(Effect::Allow)
+                }
+                _ => {}
+            }
+        }
+        decision
+    }
+}
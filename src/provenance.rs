@@ -0,0 +1,68 @@
+//! Module Provenance
+//!
+//! What the repository records about the tuples written by clients: the attributes the author
+//! authenticated with, when the tuple was written, and the attributes allowed to see it if it was
+//! written with an access control list. The tuples a client is not allowed to see are skipped by
+//! its `read`, `in`, `aggregate`, `transaction`, `move`, `copy` and `subscribe` requests.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rustupolis::tuple::{Tuple, E};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Provenance {
+    author: Vec<String>,
+    written: SystemTime,
+    readers: Option<Vec<String>>,
+}
+
+impl Provenance {
+    /// Creates the provenance of a tuple written now by the author, visible to the clients holding
+    /// one of the readers if any, and to every client otherwise.
+    pub fn new(author: Vec<String>, readers: Option<Vec<String>>) -> Provenance {
+        Provenance {
+            author,
+            written: SystemTime::now(),
+            readers,
+        }
+    }
+
+    pub fn author(&self) -> &[String] {
+        &self.author
+    }
+
+    pub fn written(&self) -> SystemTime {
+        self.written
+    }
+
+    pub fn readers(&self) -> Option<&[String]> {
+        self.readers.as_deref()
+    }
+
+    /// Returns `true` if a client holding the attributes may see the tuple.
+    pub fn allows(&self, attributes: &[String]) -> bool {
+        self.readers
+            .as_ref()
+            .is_none_or(|readers| readers.iter().any(|reader| attributes.contains(reader)))
+    }
+
+    /// Returns the provenance as a response of the `provenance` command: `(tuple, (author),
+    /// written, (readers))`, where `written` is the number of seconds since the Unix epoch and
+    /// `readers` is `nil` if every client may see the tuple.
+    pub fn to_tuple(&self, tuple: &Tuple) -> Tuple {
+        let written = self
+            .written
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |duration| duration.as_secs_f64());
+        let readers = match &self.readers {
+            Some(readers) => E::T(Tuple::from_vec(readers.iter().map(E::str).collect())),
+            None => E::None,
+        };
+        Tuple::new(&[
+            E::T(tuple.clone()),
+            E::T(Tuple::from_vec(self.author.iter().map(E::str).collect())),
+            E::D(written),
+            readers,
+        ])
+    }
+}
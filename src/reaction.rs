@@ -0,0 +1,120 @@
+//! Module Reaction
+//!
+//! Rules registered on a tuple space which react to the tuples written into it, before they are
+//! stored. A rule fires on the tuples matching its template and may transform them, drop them or
+//! write derived tuples into other tuple spaces, so that filtering and enrichment happen on the
+//! server rather than in every client.
+
+use std::sync::{Arc, RwLock};
+
+use rustupolis::tuple::Tuple;
+
+use crate::codec::tuple_elements;
+
+/// What a rule does with a tuple written into its tuple space.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reaction {
+    /// Writes this tuple instead. The next rules see the new tuple.
+    Transform(Tuple),
+    /// Does not write the tuple. The next rules are not applied, but the tuples emitted by this
+    /// rule are written.
+    Drop,
+    /// Writes these tuples into the named tuple space, whose own rules react to them.
+    Emit(String, Vec<Tuple>),
+}
+
+type Action = Arc<dyn Fn(&Tuple) -> Vec<Reaction> + Send + Sync>;
+
+struct Rule {
+    id: u64,
+    tuple_space_name: String,
+    template: Tuple,
+    action: Action,
+}
+
+/// The rules of every tuple space, applied in the order they were added.
+#[derive(Clone, Default)]
+pub struct Reactions {
+    rules: Arc<RwLock<(u64, Vec<Rule>)>>,
+}
+
+impl Reactions {
+    /// Adds a rule to the tuple space. The action is called with each tuple matching the template
+    /// and of the same arity, and the tuple is written unchanged if it returns no reaction.
+    /// Returns the id of the rule.
+    pub fn add_rule<F>(&self, tuple_space_name: &str, template: Tuple, action: F) -> u64
+    where
+        F: Fn(&Tuple) -> Vec<Reaction> + Send + Sync + 'static,
+    {
+        let mut rules = self.rules.write().unwrap();
+        let id = rules.0;
+        rules.0 += 1;
+        rules.1.push(Rule {
+            id,
+            tuple_space_name: String::from(tuple_space_name),
+            template,
+            action: Arc::new(action),
+        });
+        id
+    }
+
+    /// Returns `false` if there is no rule with this id.
+    pub fn remove_rule(&self, id: u64) -> bool {
+        let mut rules = self.rules.write().unwrap();
+        let count = rules.1.len();
+        rules.1.retain(|rule| rule.id != id);
+        rules.1.len() != count
+    }
+
+    /// Removes the rules of a deleted tuple space.
+    pub fn remove_tuple_space(&self, tuple_space_name: &str) {
+        self.rules
+            .write()
+            .unwrap()
+            .1
+            .retain(|rule| rule.tuple_space_name != tuple_space_name);
+    }
+
+    /// Applies the rules of the tuple space to a tuple. Returns the tuple to write, if it was not
+    /// dropped, and the tuples to write into other tuple spaces.
+    pub fn react(
+        &self,
+        tuple_space_name: &str,
+        tuple: Tuple,
+    ) -> (Option<Tuple>, Vec<(String, Tuple)>) {
+        // The rules are released before the actions run, so that an action may add or remove
+        // rules.
+        let rules: Vec<(Tuple, Action)> = self
+            .rules
+            .read()
+            .unwrap()
+            .1
+            .iter()
+            .filter(|rule| rule.tuple_space_name == tuple_space_name)
+            .map(|rule| (rule.template.clone(), rule.action.clone()))
+            .collect();
+        let mut tuple = tuple;
+        let mut emitted = Vec::new();
+        for (template, action) in rules {
+            if tuple_elements(&template).len() != tuple_elements(&tuple).len()
+                || !template.matches(&tuple)
+            {
+                continue;
+            }
+            let mut dropped = false;
+            for reaction in action(&tuple) {
+                match reaction {
+                    Reaction::Transform(transformed) => tuple = transformed,
+                    Reaction::Drop => dropped = true,
+                    Reaction::Emit(name, tuples) => {
+                        emitted.extend(tuples.into_iter().map(|tuple| (name.clone(), tuple)))
+                    }
+                }
+            }
+            if dropped {
+                return (None, emitted);
+            }
+        }
+        (Some(tuple), emitted)
+    }
+}
@@ -1,149 +1,619 @@
 use aes_gcm::aead::{Aead, NewAead};
 use aes_gcm::{Aes128Gcm, Key, Nonce}; // Or `Aes128Gcm`
 use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
 use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
 
-use futures::executor;
-use rustupolis::space::Space;
-use rustupolis::store::SimpleStore;
 use rustupolis::tuple;
 use rustupolis::tuple::{Tuple, E};
 
+use crate::aggregation::Aggregation;
+use crate::audit::{AuditLog, AuditRecord, ALLOW, DENY, ERROR};
+use crate::codec::Encoding;
 use crate::constant::{
-    ATTACH, CREATE, DELETE, EMPTY_REQUEST, IN, NO_MATCHING_TUPLE_FOUND, NO_PERMISSION,
-    NO_TUPLE_SPACE_ATTACHED, OUT, PERMISSION, READ, REQUEST_DOESNT_EXIST, TUPLE_IS_EMPTY,
-    TUPLE_SPACE_NOT_FOUND,
+    ACL, ACL_NOT_EXPORTED, ACL_UNSUPPORTED, AGGREGATE, AGGREGATE_IN, ATTACH, ATTRIBUTE, AUDIT,
+    AUDIT_UNAVAILABLE, AUTH, COMMAND, COPY, CREATE, CREDENTIAL_EXPIRED, DELEGABLE, DELETE,
+    DELETE_NAMESPACE, DESCRIBE, DISK_STORE_DIRECTORY, DUMP_DIRECTORY, DUMP_FAILED, EMPTY_REQUEST,
+    ENCODING, EXPORT, FIELD, FORMAT, GRANT, GROUP, IMPORT, IN, INVALID_CREDENTIAL, INVALID_OPTION,
+    INVALID_REQUEST, INVALID_TUPLE_SPACE_NAME, LIMIT, LIST, MAX_REACTION_DEPTH, METADATA, MOVE,
+    NO_MATCHING_TUPLE_FOUND, NO_PERMISSION, NO_TUPLE_SPACE_ATTACHED, OPTION_PREFIX, OUT, PEER,
+    PERMISSION, PREFIX, PROVENANCE, READ, REQUEST_DOESNT_EXIST, RESULT, SET_DESCRIPTION, SINCE,
+    SPACE, STORE_UNAVAILABLE, SUBSCRIBE, SUBSCRIPTION_UNSUPPORTED, TRANSACTION, TTL,
+    TUPLE_IS_EMPTY, TUPLE_PENDING, TUPLE_SPACE, TUPLE_SPACE_FULL, TUPLE_SPACE_NOT_FOUND,
+    UNKNOWN_AGGREGATION, UNKNOWN_ENCODING, UNKNOWN_SUBSCRIPTION, UNSUBSCRIBE,
 };
-use crate::lexing::Lexer;
-use crate::repository::RequestResponse::{DataResponse, NoResponse, OkResponse, SpaceResponse};
+use crate::credential::{Credential, VerifyingKey};
+use crate::dump::DumpFormat;
+use crate::expiration::Expirations;
+use crate::grant::{Grant, Grants};
+use crate::indexed_store::IndexedStore;
+use crate::managed_space::{ManagedSpace, OutError, SpaceLimits};
+use crate::metadata::SpaceMetadata;
+use crate::namespace;
+use crate::policy::{Effect, Policies, Policy};
+use crate::provenance::Provenance;
+use crate::reaction::{Reaction, Reactions};
+use crate::repository::RequestResponse::{
+    AuthResponse, DataResponse, EncodingResponse, NoResponse, OkResponse, SpaceResponse,
+};
+use crate::role::Roles;
+use crate::session::Session;
+use crate::store::{DiskStore, StoreKind};
+use crate::subscription::Subscriptions;
 use crate::tuple_space::TupleSpace;
 
 pub struct Repository {
-    tuple_spaces: Arc<RwLock<HashMap<String, Arc<Mutex<Space<SimpleStore>>>>>>,
-    permission_tuple_space: Arc<Mutex<Space<SimpleStore>>>,
+    tuple_spaces: Arc<RwLock<HashMap<String, Arc<Mutex<ManagedSpace>>>>>,
+    metadata: RwLock<HashMap<String, SpaceMetadata>>,
+    permission_tuple_space: Arc<Mutex<ManagedSpace>>,
+    expirations: Expirations,
+    subscriptions: Subscriptions,
+    reactions: Reactions,
+    credentials: RwLock<HashMap<String, Vec<String>>>,
+    issuers: RwLock<Vec<VerifyingKey>>,
+    roles: Roles,
+    policies: Policies,
+    grants: Grants,
+    audit_log: RwLock<Option<AuditLog>>,
 }
 
 pub enum RequestResponse {
     SpaceResponse(TupleSpace),
-    DataResponse(String),
+    EncodingResponse(Encoding),
+    AuthResponse(Vec<String>, Option<SystemTime>),
+    DataResponse(Vec<Tuple>),
     OkResponse(),
     NoResponse(String),
 }
 
+/// How to revert an operation of a transaction.
+enum Undo {
+    Remove(Tuple),
+    Restore(Tuple, Option<Provenance>),
+}
+
 impl Repository {
     pub fn new(admin_attribute: &str) -> Repository {
-        let permission = Arc::new(Mutex::new(Space::new(SimpleStore::new())));
+        let permission = Arc::new(Mutex::new(ManagedSpace::new(
+            Box::new(IndexedStore::default()),
+            SpaceLimits::unlimited(),
+        )));
         let new_repository = Repository {
             tuple_spaces: Arc::new(RwLock::new(HashMap::with_capacity(128))),
+            metadata: RwLock::new(HashMap::with_capacity(128)),
             permission_tuple_space: permission.clone(),
+            expirations: Expirations::default(),
+            subscriptions: Subscriptions::default(),
+            reactions: Reactions::default(),
+            credentials: RwLock::new(HashMap::new()),
+            issuers: RwLock::new(Vec::new()),
+            roles: Roles::default(),
+            policies: Policies::default(),
+            grants: Grants::default(),
+            audit_log: RwLock::new(None),
         };
         new_repository
             .tuple_spaces
             .write()
             .unwrap()
             .insert(String::from(PERMISSION), permission);
+        new_repository.metadata.write().unwrap().insert(
+            String::from(PERMISSION),
+            SpaceMetadata::new(
+                Some(String::from(admin_attribute)),
+                String::from("Permissions of the tuple spaces"),
+                StoreKind::default(),
+                SpaceLimits::unlimited(),
+            ),
+        );
         let mut permission_tuple_space = new_repository.permission_tuple_space.lock().unwrap();
-        let result = executor::block_on(permission_tuple_space.tuple_out(tuple!(
+        let result = permission_tuple_space.out(tuple!(
             E::str(CREATE),
             E::T(tuple!(E::str(admin_attribute)))
-        )));
+        ));
         drop(permission_tuple_space);
         new_repository.add_permission_list(vec![String::from(admin_attribute)], PERMISSION);
         match result {
             Ok(_) => new_repository,
             Err(error) => {
-                panic!("{}", error)
+                panic!("{:?}", error)
             }
         }
     }
 
-    pub fn add_tuple_space(&self, name: String, attributes: Vec<String>) {
+    /// Creates a tuple space with the given store, which fails only if a disk store cannot be
+    /// opened.
+    pub fn add_tuple_space(
+        &self,
+        name: String,
+        attributes: Vec<String>,
+        store: StoreKind,
+        limits: SpaceLimits,
+    ) -> Result<(), String> {
+        let metadata = SpaceMetadata::new(None, String::new(), store, limits);
+        self.add_tuple_space_with_metadata(name, attributes, metadata)
+    }
+
+    /// Creates a tuple space with the store and the limits of the metadata, like
+    /// `add_tuple_space`, and records the metadata.
+    pub fn add_tuple_space_with_metadata(
+        &self,
+        name: String,
+        attributes: Vec<String>,
+        metadata: SpaceMetadata,
+    ) -> Result<(), String> {
+        if !namespace::is_valid_name(&name) {
+            return Err(String::from(INVALID_TUPLE_SPACE_NAME));
+        }
+        let store = metadata.store().open()?;
         self.tuple_spaces.write().unwrap().insert(
             name.clone(),
-            Arc::new(Mutex::new(Space::new(SimpleStore::new()))),
+            Arc::new(Mutex::new(ManagedSpace::new(store, metadata.limits()))),
         );
+        self.metadata
+            .write()
+            .unwrap()
+            .insert(name.clone(), metadata);
         self.add_permission_list(attributes, name.as_str());
+        Ok(())
     }
 
+    /// Removes the tuple space along with its rules, its subscriptions and the log of a disk
+    /// store. Its permissions, grants and policies are removed too, unless tuple spaces below it
+    /// remain, which inherit them as those of their namespace. The clients attached to it get
+    /// `TUPLE_SPACE_NOT_FOUND` from then on.
     pub fn remove_tuple_space(&self, name: &str) {
-        self.tuple_spaces.write().unwrap().remove(name);
+        let descendants = {
+            let mut tuple_spaces = self.tuple_spaces.write().unwrap();
+            tuple_spaces.remove(name);
+            tuple_spaces
+                .keys()
+                .any(|other| namespace::is_in_namespace(other, name))
+        };
+        let metadata = self.metadata.write().unwrap().remove(name);
+        if let Some(StoreKind::Disk(_)) = metadata.as_ref().map(SpaceMetadata::store) {
+            let path = Path::new(DISK_STORE_DIRECTORY).join(DiskStore::file_name(name));
+            match fs::remove_file(&path) {
+                Err(error) if error.kind() != ErrorKind::NotFound => {
+                    eprintln!("Cannot remove {}: {}", path.display(), error)
+                }
+                _ => {}
+            }
+        }
+        self.reactions.remove_tuple_space(name);
+        self.subscriptions.remove_tuple_space(name);
+        if descendants {
+            return;
+        }
+        self.policies.remove_tuple_space(name);
+        self.grants.remove_tuple_space(name);
+        let mut permission_space = self.permission_tuple_space.lock().unwrap();
+        for template in [
+            tuple!(E::str(name), E::Any, E::Any),
+            tuple!(E::str(name), E::Any, E::Any, E::Any),
+        ] {
+            for permission in permission_space.read_all(&template) {
+                permission_space.remove(&permission);
+            }
+        }
     }
 
-    pub fn add_tuple_to_tuple_space(&self, tuple_space: String, tuple: Tuple) {
-        let tuple_spaces = self.tuple_spaces.read().unwrap();
-        let tuple_space = tuple_spaces.get(&*tuple_space).unwrap();
+    /// Returns `true` if the tuples of the tuple space are kept by a disk store, which does not
+    /// keep their provenance.
+    fn is_disk_backed(&self, name: &str) -> bool {
+        self.metadata
+            .read()
+            .unwrap()
+            .get(name)
+            .is_some_and(|metadata| matches!(metadata.store(), StoreKind::Disk(_)))
+    }
+
+    /// Returns `false` if the tuple space a client is attached to was deleted, even if another
+    /// one was created with the same name since.
+    fn is_current(&self, client: &TupleSpace) -> bool {
+        self.tuple_spaces
+            .read()
+            .unwrap()
+            .get(client.tuple_space_name())
+            .is_some_and(|tuple_space| Arc::ptr_eq(tuple_space, client.tuple_space()))
+    }
+
+    /// Returns the names of the tuple space and of every tuple space below it.
+    fn namespace_tuple_spaces(&self, namespace: &str) -> Vec<String> {
+        self.tuple_spaces
+            .read()
+            .unwrap()
+            .keys()
+            .filter(|name| namespace::is_in_namespace(name, namespace))
+            .cloned()
+            .collect()
+    }
+
+    /// Removes the tuple space and every tuple space below it. Returns the names removed.
+    pub fn remove_namespace(&self, namespace: &str) -> Vec<String> {
+        let names = self.namespace_tuple_spaces(namespace);
+        self.remove_tuple_spaces(&names);
+        names
+    }
+
+    /// Removes the tuple spaces, those below the others first so that the permissions of their
+    /// namespaces go too.
+    fn remove_tuple_spaces(&self, names: &[String]) {
+        let mut names = names.to_vec();
+        names.sort_by(|first, second| second.cmp(first));
+        for name in &names {
+            self.remove_tuple_space(name);
+        }
+    }
+
+    pub fn tuple_space_metadata(&self, name: &str) -> Option<SpaceMetadata> {
+        self.metadata.read().unwrap().get(name).cloned()
+    }
+
+    /// Returns `false` if the tuple space does not exist.
+    pub fn set_tuple_space_description(&self, name: &str, description: String) -> bool {
+        match self.metadata.write().unwrap().get_mut(name) {
+            Some(metadata) => {
+                metadata.set_description(description);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Adds a rule reacting to the tuples matching the template written into the tuple space,
+    /// before they are stored. Returns the id of the rule.
+    pub fn add_rule<F>(&self, tuple_space: &str, template: Tuple, action: F) -> u64
+    where
+        F: Fn(&Tuple) -> Vec<Reaction> + Send + Sync + 'static,
+    {
+        self.reactions.add_rule(tuple_space, template, action)
+    }
+
+    /// Returns `false` if there is no rule with this id.
+    pub fn remove_rule(&self, id: u64) -> bool {
+        self.reactions.remove_rule(id)
+    }
+
+    /// Writes the tuples of a tuple space into a file. Tuple spaces holding tuples with an access
+    /// control list are refused, as the dumps do not keep them. Returns the number of tuples
+    /// written, or the error response.
+    pub fn export_tuple_space(
+        &self,
+        name: &str,
+        path: &Path,
+        format: DumpFormat,
+    ) -> Result<usize, &'static str> {
+        let tuple_space = self.tuple_spaces.read().unwrap().get(name).cloned();
+        let tuple_space = tuple_space.ok_or(TUPLE_SPACE_NOT_FOUND)?;
+        let tuples = {
+            let space = tuple_space.lock().unwrap();
+            if space.has_readers() {
+                return Err(ACL_NOT_EXPORTED);
+            }
+            space.tuples()
+        };
+        format.write(path, &tuples).map_err(|error| {
+            eprintln!("Cannot write {}: {}", path.display(), error);
+            DUMP_FAILED
+        })?;
+        Ok(tuples.len())
+    }
+
+    /// Writes the tuples of a file into a tuple space, either all of them or none if one does not
+    /// fit. The rules of the tuple space do not apply. Returns the number of tuples read, or the
+    /// error response.
+    pub fn import_tuple_space(
+        &self,
+        name: &str,
+        path: &Path,
+        format: DumpFormat,
+    ) -> Result<usize, &'static str> {
+        let tuple_space = self.tuple_spaces.read().unwrap().get(name).cloned();
+        let tuple_space = tuple_space.ok_or(TUPLE_SPACE_NOT_FOUND)?;
+        let tuples = format.read(path).map_err(|error| {
+            eprintln!("Cannot read {}: {}", path.display(), error);
+            DUMP_FAILED
+        })?;
         let mut space = tuple_space.lock().unwrap();
-        let mut vec: Vec<E> = Vec::new();
-        let formatted_tuple = Repository::format_tuple(tuple, &mut vec);
-
-        executor::block_on(space.tuple_out(Tuple::from_vec(formatted_tuple.clone())))
-            .expect("ERROR - When out a value");
-    }
-
-    fn format_tuple(tuple: Tuple, formatted_tuple: &mut Vec<E>) -> &Vec<E> {
-        if !tuple.is_empty() {
-            formatted_tuple.push(match tuple.first() {
-                E::S(value) => E::S("\"".to_owned() + value + "\""),
-                E::T(tuple) => E::T(Tuple::from_vec(Repository::format_tuple(tuple.clone(), &mut formatted_tuple.clone()).clone())),
-                E::I(rest) => E::I(*rest),
-                E::D(rest) => E::D(*rest),
-                E::Any => E::Any,
-                E::None => E::None,
+        let mut undo_log = Vec::new();
+        for tuple in &tuples {
+            let present = space.read(tuple.clone()).is_some();
+            let result = match space.write(tuple.clone(), None) {
+                Ok(dropped) => {
+                    undo_log.extend(
+                        dropped
+                            .into_iter()
+                            .map(|(tuple, provenance)| Undo::Restore(tuple, provenance)),
+                    );
+                    if !present {
+                        undo_log.push(Undo::Remove(tuple.clone()));
+                    }
+                    Ok(())
+                }
+                Err(OutError::Rejected(error)) => {
+                    eprintln!("Cannot import {}: {}", tuple, error);
+                    Err(DUMP_FAILED)
+                }
+                Err(_) => Err(TUPLE_SPACE_FULL),
+            };
+            if let Err(error) = result {
+                Repository::undo(&mut space, undo_log);
+                return Err(error);
+            }
+        }
+        drop(space);
+        for undo in undo_log {
+            if let Undo::Remove(tuple) = undo {
+                self.notify(name, &tuple, None);
+            }
+        }
+        Ok(tuples.len())
+    }
+
+    /// Returns the provenance of the tuples of a tuple space matching the template, like the
+    /// `provenance` command: a `(tuple, (author), written, (readers))` tuple for each, where every
+    /// field but the tuple is `nil` for the tuples not written by a client. Returns `None` if the
+    /// tuple space does not exist.
+    pub fn tuple_provenance(&self, name: &str, template: &Tuple) -> Option<Vec<Tuple>> {
+        let tuple_space = self.tuple_spaces.read().unwrap().get(name).cloned()?;
+        let space = tuple_space.lock().unwrap();
+        let tuples = space.read_all(template);
+        Some(
+            tuples
+                .iter()
+                .map(|tuple| match space.provenance(tuple) {
+                    Some(provenance) => provenance.to_tuple(tuple),
+                    None => tuple!(E::T(tuple.clone()), E::None, E::None, E::None),
+                })
+                .collect(),
+        )
+    }
+
+    pub fn add_tuple_to_tuple_space(&self, tuple_space: String, tuple: Tuple) {
+        let tuple_space_ref = self
+            .tuple_spaces
+            .read()
+            .unwrap()
+            .get(&*tuple_space)
+            .cloned();
+        let result = match tuple_space_ref {
+            Some(tuple_space_ref) => {
+                self.write_tuple(&tuple_space, &tuple_space_ref, tuple, None, None, 0)
+            }
+            None => Err(OutError::Rejected(String::from(TUPLE_SPACE_NOT_FOUND))),
+        };
+        if let Err(error) = result {
+            eprintln!(
+                "Cannot push tuple into space! Encountered error {:?}",
+                error
+            );
+        }
+    }
+
+    /// Writes a tuple into a tuple space once its rules reacted to it, notifies the subscribers,
+    /// schedules its expiration if it has a time-to-live, then writes the tuples emitted by the
+    /// rules. Returns the tuple written, or `None` if a rule dropped it. The provenance, if any,
+    /// is recorded for the tuple written but not for the tuples emitted. A tuple which does not
+    /// fit in a tuple space whose overflow policy blocks is parked, and written by
+    /// `release_parked` once there is room.
+    fn write_tuple(
+        &self,
+        tuple_space_name: &str,
+        tuple_space: &Arc<Mutex<ManagedSpace>>,
+        tuple: Tuple,
+        provenance: Option<&Provenance>,
+        ttl: Option<Duration>,
+        depth: usize,
+    ) -> Result<Option<Tuple>, OutError> {
+        let (tuple, emitted) = self.reactions.react(tuple_space_name, tuple);
+        if let Some(tuple) = &tuple {
+            match Repository::out_tuple(tuple_space, tuple.clone(), provenance) {
+                Ok(_) => {}
+                Err(OutError::Full) => {
+                    let parked =
+                        tuple_space
+                            .lock()
+                            .unwrap()
+                            .park(tuple.clone(), provenance.cloned(), ttl);
+                    self.emit(emitted, depth);
+                    return Err(if parked {
+                        OutError::Parked
+                    } else {
+                        OutError::Full
+                    });
+                }
+                Err(error) => return Err(error),
+            }
+            self.notify(tuple_space_name, tuple, provenance);
+            if let Some(ttl) = ttl {
+                self.expirations
+                    .schedule(tuple_space_name, tuple_space, tuple.clone(), ttl);
+            }
+        }
+        self.emit(emitted, depth);
+        Ok(tuple)
+    }
+
+    /// Writes the tuples parked in a tuple space which now fit, once tuples were taken out of it
+    /// or expired, then notifies the subscribers and schedules their expiration.
+    fn release_parked(&self, tuple_space_name: &str, tuple_space: &Arc<Mutex<ManagedSpace>>) {
+        let written = tuple_space.lock().unwrap().unpark();
+        for (tuple, provenance, ttl) in written {
+            println!("pushed parked tuple {} into tuple space", tuple);
+            self.notify(tuple_space_name, &tuple, provenance.as_ref());
+            if let Some(ttl) = ttl {
+                self.expirations
+                    .schedule(tuple_space_name, tuple_space, tuple, ttl);
+            }
+        }
+    }
+
+    /// Notifies the subscribers of a tuple space of a tuple written into it, if their session may
+    /// still read the tuple space.
+    fn notify(&self, tuple_space_name: &str, tuple: &Tuple, provenance: Option<&Provenance>) {
+        self.subscriptions
+            .notify(tuple_space_name, tuple, provenance, |claimed, held| {
+                self.check_claimed_permission(READ, claimed, held, Some(tuple_space_name))
             });
-            let mut vec: Vec<E> = Vec::new();
-            let rest = Repository::format_tuple(tuple.rest().clone(), &mut vec);
-            formatted_tuple.append(&mut rest.clone());
+    }
+
+    /// Writes the tuples emitted by rules into their tuple spaces. Rules emitting into each other
+    /// are stopped after `MAX_REACTION_DEPTH` steps.
+    fn emit(&self, emitted: Vec<(String, Tuple)>, depth: usize) {
+        for (tuple_space_name, tuple) in emitted {
+            if depth >= MAX_REACTION_DEPTH {
+                eprintln!("Cannot emit tuple {}! Too many chained rules.", tuple);
+                continue;
+            }
+            let tuple_space = self
+                .tuple_spaces
+                .read()
+                .unwrap()
+                .get(&tuple_space_name)
+                .cloned();
+            let result = match tuple_space {
+                Some(tuple_space) => self.write_tuple(
+                    &tuple_space_name,
+                    &tuple_space,
+                    tuple,
+                    None,
+                    None,
+                    depth + 1,
+                ),
+                None => Err(OutError::Rejected(String::from(TUPLE_SPACE_NOT_FOUND))),
+            };
+            if let Err(error) = result {
+                eprintln!(
+                    "Cannot emit tuple into space {}! Encountered error {:?}",
+                    tuple_space_name, error
+                );
+            }
+        }
+    }
+
+    /// Writes a tuple into a tuple space, recording its provenance if any.
+    fn out_tuple(
+        tuple_space: &Mutex<ManagedSpace>,
+        tuple: Tuple,
+        provenance: Option<&Provenance>,
+    ) -> Result<Vec<Tuple>, OutError> {
+        let mut space = tuple_space.lock().unwrap();
+        match provenance {
+            Some(provenance) => space.out_with_provenance(tuple, provenance.clone()),
+            None => space.out(tuple),
         }
-        return formatted_tuple;
     }
 
     pub fn remove_tuple_to_tuple_space(&self, tuple_space: String, tuple: Tuple) {
         let tuple_spaces = self.tuple_spaces.read().unwrap();
         let tuple_space = tuple_spaces.get(&*tuple_space).unwrap();
-        let mut space = tuple_space.lock().unwrap();
-        executor::block_on(space.tuple_in(tuple)).expect("ERROR - When in a value");
+        tuple_space.lock().unwrap().take(tuple);
     }
 
     pub fn check_permission(
         &self,
         action: &str,
-        attributes: &Vec<String>,
+        attributes: &[String],
         tuple_space_name: Option<&str>,
     ) -> bool {
-        let mut permission_space = self.permission_tuple_space.lock().unwrap();
+        self.check_claimed_permission(action, attributes, attributes, tuple_space_name)
+    }
+
+    /// Checks a permission like `check_permission` for the attributes a session claimed, out of
+    /// those it holds. The policies denying the action and the `not` terms of the policies are
+    /// evaluated against every attribute held, so that claiming fewer attributes does not lift
+    /// them.
+    pub fn check_claimed_permission(
+        &self,
+        action: &str,
+        claimed: &[String],
+        held: &[String],
+        tuple_space_name: Option<&str>,
+    ) -> bool {
+        // The permissions given to an attribute are given to the roles implying it.
+        let attributes = self.roles.expand(claimed);
+        let held: Vec<String> = held.iter().chain(claimed).cloned().collect();
+        let held = self.roles.expand(&held);
+        // A policy denying the action takes precedence over every permission allowing it.
+        match self
+            .policies
+            .decide(action, tuple_space_name, &attributes, &held)
+        {
+            Some(Effect::Deny) => return false,
+            Some(Effect::Allow) => return true,
+            None => {}
+        }
+        let permission_space = self.permission_tuple_space.lock().unwrap();
         return match action {
-            CREATE => {
-                match executor::block_on(permission_space.tuple_rd(tuple!(E::str(action), E::Any)))
-                {
-                    None => false,
-                    Some(tuple) => {
-                        if tuple.is_empty() {
-                            return false;
-                        }
-                        let rest = tuple.rest();
-                        Repository::compare_attributes(rest.first(), attributes)
-                    }
-                }
-            }
-            _ => {
-                match executor::block_on(permission_space.tuple_rd(tuple!(
-                    E::str(tuple_space_name.unwrap()),
-                    E::str(action),
-                    E::Any
-                ))) {
-                    None => false,
-                    Some(tuple) => {
-                        if tuple.is_empty() {
-                            return false;
-                        }
-                        let rest = tuple.rest().rest();
-                        Repository::compare_attributes(rest.first(), attributes)
-                    }
-                }
-            }
+            CREATE => permission_space
+                .read_all(&tuple!(E::str(action), E::Any))
+                .iter()
+                .any(|tuple| Repository::compare_attributes(tuple.rest().first(), &attributes)),
+            // The permissions of a namespace apply to the tuple spaces below it, and the grants
+            // are followed by their id.
+            _ => namespace::ancestors(tuple_space_name.unwrap()).any(|name| {
+                [
+                    tuple!(E::str(name), E::str(action), E::Any),
+                    tuple!(E::str(name), E::str(action), E::Any, E::Any),
+                ]
+                .iter()
+                .flat_map(|template| permission_space.read_all(template))
+                .any(|tuple| {
+                    let rest = tuple.rest().rest();
+                    Repository::compare_attributes(rest.first(), &attributes)
+                })
+            }),
         };
     }
 
+    /// Makes the role imply the attributes, in addition to those it already implies: the clients
+    /// holding the role get the permissions of the attributes, and of those they imply in turn.
+    pub fn add_role(&self, role: &str, attributes: Vec<String>) {
+        self.roles.add(role, attributes);
+    }
+
+    /// Returns `false` if the role implies nothing.
+    pub fn remove_role(&self, role: &str) -> bool {
+        self.roles.remove(role)
+    }
+
+    /// Adds a policy allowing or denying the action to the attributes satisfying the expression,
+    /// on the tuple space or namespace, or on no tuple space for `create`. Returns the id of the
+    /// policy, or the error found while parsing the expression.
+    pub fn add_policy(
+        &self,
+        tuple_space_name: Option<&str>,
+        action: &str,
+        effect: Effect,
+        expression: &str,
+    ) -> Result<u64, String> {
+        let policy = Policy::parse(expression)?;
+        Ok(self.policies.add(tuple_space_name, action, effect, policy))
+    }
+
+    /// Returns `false` if there is no policy with this id.
+    pub fn remove_policy(&self, id: u64) -> bool {
+        self.policies.remove(id)
+    }
+
+    /// Returns the id, the action, the effect and the expression of the policies given on the
+    /// tuple space or namespace itself, or on no tuple space.
+    pub fn tuple_space_policies(
+        &self,
+        tuple_space_name: Option<&str>,
+    ) -> Vec<(u64, String, Effect, Policy)> {
+        self.policies.get(tuple_space_name)
+    }
+
+    /// Returns the attributes implied by the role, directly.
+    pub fn role_attributes(&self, role: &str) -> Vec<String> {
+        self.roles.get(role)
+    }
+
     fn compare_attributes(attributes_permission: &E, attributes_client: &Vec<String>) -> bool {
         if let E::T(tuple) = attributes_permission {
             let mut attributes_permission_list = Vec::with_capacity(156);
@@ -169,6 +639,50 @@ impl Repository {
         return false;
     }
 
+    /// Registers a credential: a client sending `auth {token}` holds these attributes. Replaces
+    /// the attributes of a previous credential with the same token.
+    pub fn add_credential(&self, token: &str, attributes: Vec<String>) {
+        self.credentials
+            .write()
+            .unwrap()
+            .insert(String::from(token), attributes);
+    }
+
+    /// Returns `false` if no credential has this token. The sessions which already authenticated
+    /// with it keep their attributes.
+    pub fn remove_credential(&self, token: &str) -> bool {
+        self.credentials.write().unwrap().remove(token).is_some()
+    }
+
+    /// Trusts the credentials signed by this key, in addition to those registered with
+    /// `add_credential`.
+    pub fn add_issuer(&self, key: VerifyingKey) {
+        self.issuers.write().unwrap().push(key);
+    }
+
+    /// Returns the attributes of the credential with this token, and when they expire for a signed
+    /// credential. Returns `None` if the token is neither registered nor a credential signed by a
+    /// trusted issuer, or if the credential expired.
+    pub fn authenticate(&self, token: &str) -> Option<(Vec<String>, Option<SystemTime>)> {
+        if let Some(attributes) = self.credentials.read().unwrap().get(token) {
+            return Some((attributes.clone(), None));
+        }
+        let credential = Credential::from_token(token).ok()?;
+        let trusted = self
+            .issuers
+            .read()
+            .unwrap()
+            .iter()
+            .any(|key| credential.is_signed_by(key));
+        match trusted && !credential.is_expired() {
+            true => Some((
+                credential.attributes().to_vec(),
+                Some(credential.expiration()),
+            )),
+            false => None,
+        }
+    }
+
     pub fn add_permission_list(&self, attributes: Vec<String>, tuple_space_name: &str) {
         if attributes.len() == 1 {
             let attribute = attributes.first().unwrap();
@@ -186,18 +700,106 @@ impl Repository {
 
     pub fn add_permission(&self, attribute: &String, action: &str, tuple_space_name: &str) {
         let mut permission_space = self.permission_tuple_space.lock().unwrap();
-        match executor::block_on(permission_space.tuple_out(tuple!(
+        match permission_space.out(tuple!(
             E::str(tuple_space_name),
             E::str(action),
             E::T(tuple!(E::S(attribute.clone())))
-        ))) {
+        )) {
             Ok(_) => {}
             Err(error) => {
-                println!("{}", error)
+                println!("{:?}", error)
             }
         }
     }
 
+    /// Gives the action on the tuple space or namespace to the attribute, until the expiration if
+    /// any, when the grant is removed from the permission tuple space. The holders of a delegable
+    /// grant may delegate it, see `delegate`. Returns the id of the grant.
+    pub fn grant(
+        &self,
+        tuple_space_name: &str,
+        action: &str,
+        attribute: &str,
+        expiration: Option<SystemTime>,
+        delegable: bool,
+    ) -> u64 {
+        let grant = self.grants.add(
+            tuple_space_name,
+            action,
+            attribute,
+            expiration,
+            delegable,
+            None,
+        );
+        self.add_grant_permission(&grant);
+        grant.id()
+    }
+
+    /// Gives the action to the attribute on behalf of the grantor attributes, which need a
+    /// delegable grant of the action on the tuple space or a namespace above it, lasting at least
+    /// as long. Returns the id of the grant, or `NO_PERMISSION`.
+    pub fn delegate(
+        &self,
+        grantor_attributes: &[String],
+        tuple_space_name: &str,
+        action: &str,
+        attribute: &str,
+        expiration: Option<SystemTime>,
+        delegable: bool,
+    ) -> Result<u64, String> {
+        let grantor_attributes = self.roles.expand(grantor_attributes);
+        let parent = self
+            .grants
+            .delegable(&grantor_attributes, tuple_space_name, action, expiration)
+            .ok_or_else(|| String::from(NO_PERMISSION))?;
+        let grant = self.grants.add(
+            tuple_space_name,
+            action,
+            attribute,
+            expiration,
+            delegable,
+            Some(parent.id()),
+        );
+        self.add_grant_permission(&grant);
+        Ok(grant.id())
+    }
+
+    fn add_grant_permission(&self, grant: &Grant) {
+        let permission = grant.to_permission();
+        if let Err(error) = self
+            .permission_tuple_space
+            .lock()
+            .unwrap()
+            .out(permission.clone())
+        {
+            println!("{:?}", error);
+            return;
+        }
+        if let Some(expiration) = grant.expiration() {
+            let ttl = expiration
+                .duration_since(SystemTime::now())
+                .unwrap_or_default();
+            self.expirations
+                .schedule(PERMISSION, &self.permission_tuple_space, permission, ttl);
+        }
+    }
+
+    /// Removes the grant and the grants delegated from it. Returns `false` if there is no grant
+    /// with this id.
+    pub fn revoke_grant(&self, id: u64) -> bool {
+        let revoked = self.grants.remove(id);
+        let mut permission_space = self.permission_tuple_space.lock().unwrap();
+        for grant in &revoked {
+            permission_space.remove(&grant.to_permission());
+        }
+        !revoked.is_empty()
+    }
+
+    /// Returns the grants on the tuple space or namespace itself which did not lapse.
+    pub fn tuple_space_grants(&self, tuple_space_name: &str) -> Vec<Grant> {
+        self.grants.get(tuple_space_name)
+    }
+
     fn decrypt_data(key: &str, text: &[u8]) -> Vec<u8> {
         let key = Key::from_slice(key.as_ref());
         let cipher = Aes128Gcm::new(key);
@@ -210,203 +812,1332 @@ impl Repository {
         };
     }
 
-    pub fn manage_request(
+    /// Splits a request between its command and its parameters.
+    fn split_command(request: &[u8]) -> (&[u8], &[u8]) {
+        let start = request
+            .iter()
+            .position(|byte| !byte.is_ascii_whitespace())
+            .unwrap_or(request.len());
+        let request = &request[start..];
+        match request.iter().position(|byte| byte.is_ascii_whitespace()) {
+            Some(end) => (&request[..end], &request[end + 1..]),
+            None => (request, &[]),
+        }
+    }
+
+    /// Splits the `name=value` options written before the tuples of a request.
+    fn split_options(mut payload: &[u8]) -> (Vec<(&str, &str)>, &[u8]) {
+        let mut options = Vec::new();
+        loop {
+            let start = payload
+                .iter()
+                .position(|byte| !byte.is_ascii_whitespace())
+                .unwrap_or(payload.len());
+            payload = &payload[start..];
+            if !payload
+                .first()
+                .is_some_and(|byte| byte.is_ascii_alphabetic())
+            {
+                return (options, payload);
+            }
+            let end = payload
+                .iter()
+                .position(|byte| byte.is_ascii_whitespace())
+                .unwrap_or(payload.len());
+            match std::str::from_utf8(&payload[..end]) {
+                Ok(option) => options.push(option.split_once('=').unwrap_or((option, ""))),
+                Err(_) => return (options, payload),
+            }
+            payload = &payload[end..];
+        }
+    }
+
+    /// Splits the operations of a transaction, each one being a command followed by its tuples.
+    /// Operations may be separated by semicolons.
+    fn split_operations(
+        encoding: Encoding,
+        mut payload: &[u8],
+    ) -> Result<Vec<(&str, Vec<Tuple>)>, String> {
+        let mut operations = Vec::new();
+        loop {
+            let start = payload
+                .iter()
+                .position(|byte| !byte.is_ascii_whitespace() && *byte != b';')
+                .unwrap_or(payload.len());
+            payload = &payload[start..];
+            if payload.is_empty() {
+                return Ok(operations);
+            }
+            let (operation, rest) = Repository::split_command(payload);
+            let operation = std::str::from_utf8(operation).map_err(|error| error.to_string())?;
+            let (tuples, length) = encoding.decode_leading_tuples(rest)?;
+            operations.push((operation, tuples));
+            payload = &rest[length..];
+        }
+    }
+
+    /// Applies the operations of a transaction under a single lock of the tuple space. If one of
+    /// them fails, the ones already applied are undone and the error is returned.
+    fn run_transaction(
         &self,
-        request: &[u8],
-        client_option: Option<&TupleSpace>,
-        key: &str,
+        client: &TupleSpace,
+        author: &[String],
+        operations: Vec<(&str, Vec<Tuple>)>,
     ) -> RequestResponse {
-        let request_str = &*Repository::decrypt_data(key, request).clone();
-        let request = std::str::from_utf8(request_str).unwrap();
-        let words: Vec<&str> = request.split_whitespace().collect();
-        if words.len() != 0 {
-            match words[0] {
-                CREATE => {
-                    let attribute_to_create = String::from(words[1]).replace("\"", "");
-                    if self.check_permission(CREATE, &vec![attribute_to_create], None) {
-                        let mut attributes_list: Vec<String> = Vec::new();
-                        for index in 3..words.len() {
-                            attributes_list.push(String::from(words[index]));
-                        }
-                        self.add_tuple_space(String::from(words[2]), attributes_list);
-                        OkResponse()
-                    } else {
-                        NoResponse(String::from(NO_PERMISSION))
+        for (operation, _) in &operations {
+            if !matches!(*operation, OUT | READ | IN) {
+                return NoResponse(String::from(REQUEST_DOESNT_EXIST));
+            }
+            if !self.check_claimed_permission(
+                operation,
+                client.attributes(),
+                author,
+                Some(client.tuple_space_name()),
+            ) {
+                return NoResponse(String::from(NO_PERMISSION));
+            }
+        }
+        // The rules react to the tuples written before the tuple space is locked, and the tuples
+        // they emit are only written once the transaction is applied.
+        let mut emitted = Vec::new();
+        let operations: Vec<(&str, Vec<Tuple>)> = operations
+            .into_iter()
+            .map(|(operation, tuples)| {
+                if operation != OUT {
+                    return (operation, tuples);
+                }
+                let tuples = tuples
+                    .into_iter()
+                    .filter_map(|tuple| {
+                        let (tuple, mut derived) =
+                            self.reactions.react(client.tuple_space_name(), tuple);
+                        emitted.append(&mut derived);
+                        tuple
+                    })
+                    .collect();
+                (operation, tuples)
+            })
+            .collect();
+        let attributes = self.roles.expand(client.attributes());
+        let provenance = Provenance::new(author.to_vec(), None);
+        let mut space = client.tuple_space().lock().unwrap();
+        let mut undo_log = Vec::new();
+        let mut tuple_list = Vec::new();
+        for (operation, tuples) in operations {
+            for tuple in tuples {
+                if let Err(error) = Repository::apply(
+                    &mut space,
+                    operation,
+                    tuple,
+                    &attributes,
+                    &provenance,
+                    &mut undo_log,
+                    &mut tuple_list,
+                ) {
+                    Repository::undo(&mut space, undo_log);
+                    return NoResponse(String::from(error));
+                }
+            }
+        }
+        drop(space);
+        println!("transaction applied");
+        for undo in undo_log {
+            if let Undo::Remove(tuple) = undo {
+                self.notify(client.tuple_space_name(), &tuple, Some(&provenance));
+            }
+        }
+        self.emit(emitted, 0);
+        if tuple_list.is_empty() {
+            OkResponse()
+        } else {
+            DataResponse(tuple_list)
+        }
+    }
+
+    /// Returns the actions the attributes claimed, out of those held, are allowed on the tuple
+    /// space.
+    fn permissions(
+        &self,
+        attributes: &[String],
+        held: &[String],
+        tuple_space_name: &str,
+    ) -> Vec<E> {
+        [READ, IN, OUT, DELETE]
+            .into_iter()
+            .filter(|action| {
+                self.check_claimed_permission(action, attributes, held, Some(tuple_space_name))
+            })
+            .map(E::str)
+            .collect()
+    }
+
+    /// Describes the tuple spaces whose name passes the filter and the attributes give any
+    /// permission on, sorted by name. Each tuple space is described by its name, its number of
+    /// tuples and the actions allowed, and with `detailed` by the size of its tuples too.
+    fn describe<F>(
+        &self,
+        attributes: &[String],
+        held: &[String],
+        filter: F,
+        detailed: bool,
+    ) -> Vec<Tuple>
+    where
+        F: Fn(&str) -> bool,
+    {
+        let mut tuple_spaces: Vec<(String, Arc<Mutex<ManagedSpace>>)> = self
+            .tuple_spaces
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(name, _)| filter(name))
+            .map(|(name, space)| (name.clone(), space.clone()))
+            .collect();
+        tuple_spaces.sort_by(|(first, _), (second, _)| first.cmp(second));
+        let mut descriptions = Vec::new();
+        for (name, space) in tuple_spaces {
+            let permissions = self.permissions(attributes, held, &name);
+            if permissions.is_empty() {
+                continue;
+            }
+            let space = space.lock().unwrap();
+            let mut description = vec![E::str(&name), E::I(space.len() as i32)];
+            if detailed {
+                description.push(E::I(space.size() as i32));
+            }
+            description.push(E::T(Tuple::from_vec(permissions)));
+            descriptions.push(Tuple::from_vec(description));
+        }
+        descriptions
+    }
+
+    /// Reverts the operations logged, the last one first.
+    fn undo(space: &mut ManagedSpace, undo_log: Vec<Undo>) {
+        for undo in undo_log.into_iter().rev() {
+            match undo {
+                Undo::Remove(tuple) => {
+                    space.remove(&tuple);
+                }
+                Undo::Restore(tuple, provenance) => {
+                    let _ = space.write(tuple, provenance);
+                }
+            }
+        }
+    }
+
+    /// Writes the tuples of a tuple space matching the template into another one, and takes them
+    /// out of the first one if `consume` is set. Either every tuple is transferred or none. The
+    /// tuples keep their provenance, and get the author's if they have none, and their remaining
+    /// time-to-live.
+    fn transfer(
+        &self,
+        client: &TupleSpace,
+        author: &[String],
+        source_name: &str,
+        destination_name: &str,
+        template: Tuple,
+        consume: bool,
+    ) -> RequestResponse {
+        if source_name == destination_name {
+            return NoResponse(String::from(INVALID_REQUEST));
+        }
+        let (source, destination) = {
+            let tuple_spaces = self.tuple_spaces.read().unwrap();
+            match (
+                tuple_spaces.get(source_name),
+                tuple_spaces.get(destination_name),
+            ) {
+                (Some(source), Some(destination)) => (source.clone(), destination.clone()),
+                _ => return NoResponse(String::from(TUPLE_SPACE_NOT_FOUND)),
+            }
+        };
+        let source_permission = if consume { IN } else { READ };
+        if !self.check_claimed_permission(
+            source_permission,
+            client.attributes(),
+            author,
+            Some(source_name),
+        ) || !self.check_claimed_permission(
+            OUT,
+            client.attributes(),
+            author,
+            Some(destination_name),
+        ) {
+            return NoResponse(String::from(NO_PERMISSION));
+        }
+
+        let disk_backed = self.is_disk_backed(destination_name);
+        // Both tuple spaces are locked in the order of their names, so that concurrent transfers
+        // cannot deadlock.
+        let (source_space, destination_space) = (source, destination);
+        let (mut source, mut destination) = if source_name < destination_name {
+            let source = source_space.lock().unwrap();
+            (source, destination_space.lock().unwrap())
+        } else {
+            let destination = destination_space.lock().unwrap();
+            (source_space.lock().unwrap(), destination)
+        };
+        let attributes = self.roles.expand(client.attributes());
+        let tuples = source.read_all_visible(&template, &attributes);
+        if tuples.is_empty() {
+            return NoResponse(String::from(NO_MATCHING_TUPLE_FOUND));
+        }
+        let provenances: Vec<Provenance> = tuples
+            .iter()
+            .map(|tuple| {
+                source
+                    .provenance(tuple)
+                    .cloned()
+                    .unwrap_or_else(|| Provenance::new(author.to_vec(), None))
+            })
+            .collect();
+        if disk_backed
+            && provenances
+                .iter()
+                .any(|provenance| provenance.readers().is_some())
+        {
+            return NoResponse(String::from(ACL_UNSUPPORTED));
+        }
+        let deadlines: Vec<Option<Instant>> =
+            tuples.iter().map(|tuple| source.deadline(tuple)).collect();
+        let mut source_log = Vec::new();
+        let mut destination_log = Vec::new();
+        let mut tuple_list = Vec::new();
+        for (tuple, provenance) in tuples.iter().zip(&provenances) {
+            let result = if consume {
+                Repository::apply(
+                    &mut source,
+                    IN,
+                    tuple.clone(),
+                    &attributes,
+                    provenance,
+                    &mut source_log,
+                    &mut tuple_list,
+                )
+            } else {
+                Ok(())
+            };
+            if let Err(error) = result.and_then(|_| {
+                Repository::apply(
+                    &mut destination,
+                    OUT,
+                    tuple.clone(),
+                    &attributes,
+                    provenance,
+                    &mut destination_log,
+                    &mut tuple_list,
+                )
+            }) {
+                Repository::undo(&mut destination, destination_log);
+                Repository::undo(&mut source, source_log);
+                return NoResponse(String::from(error));
+            }
+        }
+        drop(source);
+        drop(destination);
+        if consume {
+            self.release_parked(source_name, &source_space);
+        }
+        // The tuples expire from the destination when they would have from the source.
+        let now = Instant::now();
+        for (tuple, deadline) in tuples.iter().zip(deadlines) {
+            if let Some(deadline) = deadline {
+                self.expirations.schedule(
+                    destination_name,
+                    &destination_space,
+                    tuple.clone(),
+                    deadline.saturating_duration_since(now),
+                );
+            }
+        }
+        for (tuple, provenance) in tuples.iter().zip(&provenances) {
+            self.notify(destination_name, tuple, Some(provenance));
+        }
+        println!(
+            "transferred {} tuples from {} to {}",
+            tuples.len(),
+            source_name,
+            destination_name
+        );
+        DataResponse(tuples)
+    }
+
+    /// Applies one operation of a transaction, logging how to undo it. The tuples written get the
+    /// provenance, and the tuples read or taken are those the attributes may see.
+    fn apply(
+        space: &mut ManagedSpace,
+        operation: &str,
+        tuple: Tuple,
+        attributes: &[String],
+        provenance: &Provenance,
+        undo_log: &mut Vec<Undo>,
+        tuple_list: &mut Vec<Tuple>,
+    ) -> Result<(), &'static str> {
+        if tuple.is_empty() {
+            return Err(TUPLE_IS_EMPTY);
+        }
+        match operation {
+            OUT => {
+                let present = space.read(tuple.clone()).is_some();
+                match space.write(tuple.clone(), Some(provenance.clone())) {
+                    Ok(dropped) => {
+                        undo_log.extend(
+                            dropped
+                                .into_iter()
+                                .map(|(tuple, provenance)| Undo::Restore(tuple, provenance)),
+                        );
+                        if !present {
+                            undo_log.push(Undo::Remove(tuple));
+                        }
+                        Ok(())
                     }
+                    Err(OutError::Rejected(_)) => Err(INVALID_REQUEST),
+                    Err(_) => Err(TUPLE_SPACE_FULL),
+                }
+            }
+            READ => {
+                let tuple = space.read_visible(tuple, attributes);
+                tuple_list.push(tuple.ok_or(NO_MATCHING_TUPLE_FOUND)?);
+                Ok(())
+            }
+            _ => {
+                let tuple = space
+                    .read_visible(tuple, attributes)
+                    .ok_or(NO_MATCHING_TUPLE_FOUND)?;
+                let provenance = space.provenance(&tuple).cloned();
+                space.remove(&tuple);
+                undo_log.push(Undo::Restore(tuple.clone(), provenance));
+                tuple_list.push(tuple);
+                Ok(())
+            }
+        }
+    }
+
+    /// Aggregates the tuples matching the template of an `aggregate` request, and takes them out
+    /// of the tuple space if `consume` is set.
+    fn aggregate(
+        &self,
+        client: &TupleSpace,
+        encoding: Encoding,
+        payload: &[u8],
+        consume: bool,
+    ) -> RequestResponse {
+        let (function, payload) = Repository::split_command(payload);
+        let (options, payload) = Repository::split_options(payload);
+        let mut field = None;
+        let mut group = None;
+        for (name, value) in options {
+            match (name, value.parse::<usize>()) {
+                (FIELD, Ok(value)) => field = Some(value),
+                (GROUP, Ok(value)) => group = Some(value),
+                _ => return NoResponse(String::from(INVALID_OPTION)),
+            }
+        }
+        let aggregation = match std::str::from_utf8(function)
+            .ok()
+            .and_then(|function| Aggregation::from_name(function, field))
+        {
+            Some(aggregation) => aggregation,
+            None => return NoResponse(String::from(UNKNOWN_AGGREGATION)),
+        };
+        let template = match encoding.decode_tuples(payload) {
+            Ok(mut templates) if templates.len() == 1 => templates.remove(0),
+            _ => return NoResponse(String::from(INVALID_REQUEST)),
+        };
+        if template.is_empty() {
+            return NoResponse(String::from(TUPLE_IS_EMPTY));
+        }
+
+        let attributes = self.roles.expand(client.attributes());
+        let mut space = client.tuple_space().lock().unwrap();
+        let tuples = space.read_all_visible(&template, &attributes);
+        match aggregation.aggregate(&tuples, group) {
+            Some(result) => {
+                if consume {
+                    for tuple in &tuples {
+                        space.remove(tuple);
+                    }
+                }
+                println!(
+                    "{} of {} tuples: {}",
+                    aggregation.name(),
+                    tuples.len(),
+                    result
+                );
+                DataResponse(vec![result])
+            }
+            None => NoResponse(String::from(NO_MATCHING_TUPLE_FOUND)),
+        }
+    }
+
+    /// Removes the tuples and permissions whose time-to-live is over at the given instant, then
+    /// writes the tuples parked in the tuple spaces which now have room.
+    pub fn sweep_expirations(&self, now: Instant) {
+        for (tuple_space_name, tuple_space) in self.expirations.sweep(now) {
+            self.release_parked(&tuple_space_name, &tuple_space);
+        }
+    }
+
+    /// Forgets the subscriptions of a client whose connection is closed.
+    pub fn close_session(&self, session: &Session) {
+        self.subscriptions.close_session(session);
+    }
+
+    /// Splits the `--name=value` options written before the attributes of a word command from the
+    /// attributes, and strips their prefix. The options end at the first word without the prefix,
+    /// or at a `--` word so that the attributes may start with it.
+    fn split_word_options<'a>(words: &[&'a str]) -> (Vec<&'a str>, Vec<&'a str>) {
+        let mut options = Vec::new();
+        let mut rest = words;
+        while let Some((word, others)) = rest.split_first() {
+            if *word == OPTION_PREFIX {
+                rest = others;
+                break;
+            }
+            match word.strip_prefix(OPTION_PREFIX) {
+                Some(option) => options.push(option),
+                None => break,
+            }
+            rest = others;
+        }
+        (options, rest.to_vec())
+    }
+
+    /// Reads the parameters of the `export` and `import` commands following the tuple space name:
+    /// the file name, which must name a file of `DUMP_DIRECTORY`, then the options and the
+    /// attributes.
+    fn dump_parameters<'a>(
+        words: &[&'a str],
+    ) -> Result<(std::path::PathBuf, DumpFormat, Vec<&'a str>), &'static str> {
+        let file_name = words.first().ok_or(INVALID_REQUEST)?;
+        if file_name.starts_with('.')
+            || !file_name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+        {
+            return Err(INVALID_REQUEST);
+        }
+        let (options, attributes) = Repository::split_word_options(&words[1..]);
+        let mut format = DumpFormat::default();
+        for option in options {
+            match option.split_once('=') {
+                Some((FORMAT, name)) => {
+                    format = DumpFormat::from_name(name).ok_or(INVALID_OPTION)?
                 }
-                DELETE => {
-                    let attribute_to_delete = String::from(words[1]);
-                    // TODO check attributes
-                    if self.check_permission(DELETE, &vec![attribute_to_delete], Some(words[2])) {
-                        self.remove_tuple_space(words[2]);
-                        OkResponse()
+                _ => return Err(INVALID_OPTION),
+            }
+        }
+        Ok((
+            Path::new(DUMP_DIRECTORY).join(file_name),
+            format,
+            attributes,
+        ))
+    }
+
+    /// Returns the attributes written in a request, or all those of the session if there are
+    /// none. Returns `None` if the session did not authenticate with one of them or with a role
+    /// implying it.
+    fn claimed_attributes(&self, session: &Session, words: &[&str]) -> Option<Vec<String>> {
+        let held = self.roles.expand(session.attributes());
+        words
+            .iter()
+            .map(|word| held.iter().find(|attribute| attribute == word).cloned())
+            .collect::<Option<Vec<String>>>()
+            .map(|attributes| match attributes.is_empty() {
+                true => session.attributes().to_vec(),
+                false => attributes,
+            })
+    }
+
+    /// Returns the attributes written in a request, or those of the attached tuple space if there
+    /// are none, like `claimed_attributes`. Returns `None` once the credential expired.
+    fn request_attributes(&self, session: &Session, words: &[&str]) -> Option<Vec<String>> {
+        match (words.is_empty(), session.tuple_space()) {
+            (true, Some(_)) if session.is_expired() => None,
+            (true, Some(client)) => Some(client.attributes().clone()),
+            _ => self.claimed_attributes(session, words),
+        }
+    }
+
+    /// Records every request from now on in the audit log at this path, rotated once it reaches
+    /// `max_bytes` and keeping `max_files` previous files.
+    pub fn enable_audit_log(
+        &self,
+        path: &Path,
+        max_bytes: u64,
+        max_files: usize,
+    ) -> Result<(), String> {
+        let audit_log = AuditLog::open(path, max_bytes, max_files)?;
+        *self.audit_log.write().unwrap() = Some(audit_log);
+        Ok(())
+    }
+
+    /// Returns the records of the audit log passing the filter, the oldest first, at most `limit`
+    /// of the most recent ones if any, and the number of malformed lines skipped.
+    pub fn audit_records<F>(
+        &self,
+        filter: F,
+        limit: Option<usize>,
+    ) -> Result<(Vec<AuditRecord>, usize), String>
+    where
+        F: Fn(&AuditRecord) -> bool,
+    {
+        match self.audit_log.read().unwrap().as_ref() {
+            Some(audit_log) => audit_log.query(filter, limit),
+            None => Err(String::from(AUDIT_UNAVAILABLE)),
+        }
+    }
+
+    /// Records a request and its response in the audit log, if it is enabled.
+    fn audit(&self, request: &[u8], session: &Session, response: &RequestResponse) {
+        let audit_log = self.audit_log.read().unwrap();
+        let audit_log = match audit_log.as_ref() {
+            Some(audit_log) => audit_log,
+            None => return,
+        };
+        let (command, payload) = Repository::split_command(request);
+        let (first, payload) = Repository::split_command(payload);
+        let (second, _) = Repository::split_command(payload);
+        let command = String::from_utf8_lossy(command).into_owned();
+        let tuple_space = match command.as_str() {
+            OUT | READ | IN | AGGREGATE | AGGREGATE_IN | TRANSACTION | SUBSCRIBE | UNSUBSCRIBE => {
+                session
+                    .tuple_space()
+                    .map(|client| String::from(client.tuple_space_name()))
+            }
+            ATTACH | METADATA | DESCRIBE | MOVE | COPY => {
+                Some(String::from_utf8_lossy(first).into_owned())
+            }
+            CREATE | DELETE | DELETE_NAMESPACE | EXPORT | IMPORT | SET_DESCRIPTION | PROVENANCE
+            | GRANT => Some(String::from_utf8_lossy(second).into_owned()),
+            _ => None,
+        }
+        .filter(|name| !name.is_empty());
+        let attributes = match response {
+            AuthResponse(attributes, _) => attributes.clone(),
+            _ => session.attributes().to_vec(),
+        };
+        let (result, message) = match response {
+            NoResponse(message)
+                if [NO_PERMISSION, INVALID_CREDENTIAL, CREDENTIAL_EXPIRED]
+                    .contains(&message.as_str()) =>
+            {
+                (DENY, Some(message.clone()))
+            }
+            NoResponse(message) => (ERROR, Some(message.clone())),
+            _ => (ALLOW, None),
+        };
+        let record = AuditRecord::new(
+            session.peer().map(|peer| peer.to_string()),
+            session.protocol().map(String::from),
+            attributes,
+            command,
+            tuple_space,
+            result,
+            message,
+        );
+        if let Err(error) = audit_log.record(&record) {
+            eprintln!("Cannot write the audit log: {}", error);
+        }
+    }
+
+    pub fn manage_request(&self, request: &[u8], session: &Session, key: &str) -> RequestResponse {
+        let request = Repository::decrypt_data(key, request);
+        let response = self.handle_request(&request, session);
+        self.audit(&request, session, &response);
+        response
+    }
+
+    fn handle_request(&self, request: &[u8], session: &Session) -> RequestResponse {
+        let (command, payload) = Repository::split_command(request);
+        let command = match std::str::from_utf8(command) {
+            Ok(command) => command,
+            Err(_) => return NoResponse(String::from(INVALID_REQUEST)),
+        };
+        if let Some(client) = session.tuple_space() {
+            if matches!(
+                command,
+                OUT | READ | IN | AGGREGATE | AGGREGATE_IN | TRANSACTION | SUBSCRIBE | MOVE | COPY
+            ) {
+                if session.is_expired() {
+                    return NoResponse(String::from(CREDENTIAL_EXPIRED));
+                }
+                if !self.is_current(client) {
+                    return NoResponse(String::from(TUPLE_SPACE_NOT_FOUND));
+                }
+            }
+        }
+        match command {
+            "" => NoResponse(String::from(EMPTY_REQUEST)),
+            OUT => {
+                if let Some(client) = session.tuple_space() {
+                    if self.check_claimed_permission(
+                        OUT,
+                        client.attributes(),
+                        session.attributes(),
+                        Some(client.tuple_space_name()),
+                    ) {
+                        let (options, payload) = Repository::split_options(payload);
+                        let mut ttl = None;
+                        let mut readers = None;
+                        for (name, value) in options {
+                            match (name, value.parse::<f64>().map(Duration::try_from_secs_f64)) {
+                                (TTL, Ok(Ok(duration))) => ttl = Some(duration),
+                                (ACL, _) => {
+                                    readers = Some(value.split(',').map(String::from).collect())
+                                }
+                                _ => return NoResponse(String::from(INVALID_OPTION)),
+                            }
+                        }
+                        if readers.is_some() && self.is_disk_backed(client.tuple_space_name()) {
+                            return NoResponse(String::from(ACL_UNSUPPORTED));
+                        }
+                        let provenance = Provenance::new(session.attributes().to_vec(), readers);
+                        let tuple_list = match session.encoding().decode_tuples(payload) {
+                            Ok(tuple_list) => tuple_list,
+                            Err(_) => return NoResponse(String::from(INVALID_REQUEST)),
+                        };
+                        let mut parked = false;
+                        for tuple in tuple_list {
+                            if !tuple.is_empty() {
+                                if tuple.is_defined() {
+                                    match self.write_tuple(
+                                        client.tuple_space_name(),
+                                        client.tuple_space(),
+                                        tuple,
+                                        Some(&provenance),
+                                        ttl,
+                                        0,
+                                    ) {
+                                        Ok(None) => println!("tuple dropped by a rule"),
+                                        Ok(Some(tuple)) => {
+                                            println!("pushed tuple {} into tuple space", tuple)
+                                        }
+                                        Err(OutError::Parked) => parked = true,
+                                        Err(OutError::Full) => {
+                                            return NoResponse(String::from(TUPLE_SPACE_FULL))
+                                        }
+                                        Err(error) => eprintln!(
+                                            "Cannot push tuple into space! Encountered error {:?}",
+                                            error
+                                        ),
+                                    }
+                                } else {
+                                    eprintln!("Cannot push tuple into space! The given tuple is ill-defined.");
+                                }
+                            }
+                        }
+                        if parked {
+                            NoResponse(String::from(TUPLE_PENDING))
+                        } else {
+                            OkResponse()
+                        }
                     } else {
                         NoResponse(String::from(NO_PERMISSION))
                     }
+                } else {
+                    NoResponse(String::from(NO_TUPLE_SPACE_ATTACHED))
                 }
-                ATTACH => {
-                    let tuple_spaces = self.tuple_spaces.read().unwrap();
-                    let tuple_space_found = tuple_spaces.get(words[1]);
-                    match tuple_space_found {
-                        None => NoResponse(String::from(TUPLE_SPACE_NOT_FOUND)),
-                        Some(tuple_space_ref) => {
-                            let mut attributes_list: Vec<String> = Vec::new();
-                            for index in 2..words.len() {
-                                attributes_list.push(String::from(words[index]));
+            }
+            READ => {
+                if let Some(client) = session.tuple_space() {
+                    if self.check_claimed_permission(
+                        READ,
+                        client.attributes(),
+                        session.attributes(),
+                        Some(client.tuple_space_name()),
+                    ) {
+                        let mut tuples = match session.encoding().decode_tuples(payload) {
+                            Ok(tuples) => tuples,
+                            Err(_) => return NoResponse(String::from(INVALID_REQUEST)),
+                        };
+                        let attributes = self.roles.expand(client.attributes());
+                        let mut response: RequestResponse = NoResponse(String::from(""));
+                        let mut tuple_list: Vec<Tuple> = Vec::new();
+                        for i in (0..tuples.len()).rev() {
+                            let rd_tup: Tuple = tuples.remove(i);
+                            if !rd_tup.is_empty() {
+                                let mut space = client.tuple_space().lock().unwrap();
+                                match space.read_visible(rd_tup, &attributes) {
+                                    None => {
+                                        response =
+                                            NoResponse(String::from(NO_MATCHING_TUPLE_FOUND));
+                                    }
+                                    Some(match_tup) => {
+                                        println!("reading tuples {} from space", match_tup);
+                                        tuple_list.push(match_tup);
+                                    }
+                                }
+                            } else {
+                                response = NoResponse(String::from(TUPLE_IS_EMPTY));
                             }
-                            SpaceResponse(TupleSpace::new(
-                                tuple_space_ref.clone(),
-                                attributes_list,
-                                words[1],
-                            ))
                         }
+                        if tuple_list.is_empty() {
+                            response
+                        } else {
+                            DataResponse(tuple_list)
+                        }
+                    } else {
+                        NoResponse(String::from(NO_PERMISSION))
                     }
+                } else {
+                    NoResponse(String::from(NO_TUPLE_SPACE_ATTACHED))
                 }
-                OUT => {
-                    if let Some(client) = client_option {
-                        if self.check_permission(
-                            OUT,
-                            client.attributes(),
-                            Some(client.tuple_space_name()),
-                        ) {
-                            let param_list = words[1..].join(" ");
-                            let tuple_list: Vec<Tuple> = Lexer::new(&param_list).collect();
-                            for tuple in tuple_list {
-                                if !tuple.is_empty() {
-                                    if tuple.is_defined() {
-                                        let mut space = client.tuple_space().lock().unwrap();
-                                        if let Err(error) =
-                                            executor::block_on(space.tuple_out(tuple))
-                                        {
-                                            eprintln!(
-                                                "Cannot push tuple into space! Encountered error {:?}",
-                                                error
-                                            );
-                                        } else {
-                                            println!(
-                                                "pushed tuple(s) {} into tuple space",
-                                                param_list
-                                            );
-                                        }
-                                    } else {
-                                        eprintln!("Cannot push tuple into space! The given tuple is ill-defined.");
+            }
+            IN => {
+                if let Some(client) = session.tuple_space() {
+                    if self.check_claimed_permission(
+                        IN,
+                        client.attributes(),
+                        session.attributes(),
+                        Some(client.tuple_space_name()),
+                    ) {
+                        let mut tuples = match session.encoding().decode_tuples(payload) {
+                            Ok(tuples) => tuples,
+                            Err(_) => return NoResponse(String::from(INVALID_REQUEST)),
+                        };
+                        let attributes = self.roles.expand(client.attributes());
+                        let mut response: RequestResponse = NoResponse(String::from(""));
+                        let mut tuple_list: Vec<Tuple> = Vec::new();
+                        for i in (0..tuples.len()).rev() {
+                            let rd_tup: Tuple = tuples.remove(i);
+                            if !rd_tup.is_empty() {
+                                let mut space = client.tuple_space().lock().unwrap();
+                                println!("pulling in tuple matching {} from space", &rd_tup);
+                                match space.take_visible(rd_tup, &attributes) {
+                                    None => {
+                                        response =
+                                            NoResponse(String::from(NO_MATCHING_TUPLE_FOUND));
                                     }
+                                    Some(match_tup) => tuple_list.push(match_tup),
                                 }
+                            } else {
+                                response = NoResponse(String::from(TUPLE_IS_EMPTY));
                             }
+                        }
+                        self.release_parked(client.tuple_space_name(), client.tuple_space());
+                        if tuple_list.is_empty() {
+                            response
+                        } else {
+                            DataResponse(tuple_list)
+                        }
+                    } else {
+                        NoResponse(String::from(NO_PERMISSION))
+                    }
+                } else {
+                    NoResponse(String::from(NO_TUPLE_SPACE_ATTACHED))
+                }
+            }
+            AGGREGATE | AGGREGATE_IN => {
+                if let Some(client) = session.tuple_space() {
+                    let action = if command == AGGREGATE { READ } else { IN };
+                    if self.check_claimed_permission(
+                        action,
+                        client.attributes(),
+                        session.attributes(),
+                        Some(client.tuple_space_name()),
+                    ) {
+                        let response =
+                            self.aggregate(client, session.encoding(), payload, action == IN);
+                        if action == IN {
+                            self.release_parked(client.tuple_space_name(), client.tuple_space());
+                        }
+                        response
+                    } else {
+                        NoResponse(String::from(NO_PERMISSION))
+                    }
+                } else {
+                    NoResponse(String::from(NO_TUPLE_SPACE_ATTACHED))
+                }
+            }
+            SUBSCRIBE => {
+                if let Some(client) = session.tuple_space() {
+                    if self.check_claimed_permission(
+                        READ,
+                        client.attributes(),
+                        session.attributes(),
+                        Some(client.tuple_space_name()),
+                    ) {
+                        let (id, payload) = Repository::split_command(payload);
+                        let id = match std::str::from_utf8(id).map(str::parse::<u64>) {
+                            Ok(Ok(id)) => id,
+                            _ => return NoResponse(String::from(INVALID_REQUEST)),
+                        };
+                        let template = match session.encoding().decode_tuples(payload) {
+                            Ok(mut tuples) if tuples.len() == 1 => tuples.remove(0),
+                            _ => return NoResponse(String::from(INVALID_REQUEST)),
+                        };
+                        let attributes = self.roles.expand(client.attributes());
+                        if self
+                            .subscriptions
+                            .subscribe(session, id, template, attributes)
+                        {
                             OkResponse()
                         } else {
-                            NoResponse(String::from(NO_PERMISSION))
+                            NoResponse(String::from(SUBSCRIPTION_UNSUPPORTED))
                         }
                     } else {
-                        NoResponse(String::from(NO_TUPLE_SPACE_ATTACHED))
+                        NoResponse(String::from(NO_PERMISSION))
                     }
+                } else {
+                    NoResponse(String::from(NO_TUPLE_SPACE_ATTACHED))
                 }
-                READ => {
-                    if let Some(client) = client_option {
-                        if self.check_permission(
-                            READ,
-                            client.attributes(),
-                            Some(client.tuple_space_name()),
+            }
+            UNSUBSCRIBE => match std::str::from_utf8(payload).map(|id| id.trim().parse::<u64>()) {
+                Ok(Ok(id)) if self.subscriptions.unsubscribe(session, id) => OkResponse(),
+                Ok(Ok(_)) => NoResponse(String::from(UNKNOWN_SUBSCRIPTION)),
+                _ => NoResponse(String::from(INVALID_REQUEST)),
+            },
+            MOVE | COPY => {
+                if let Some(client) = session.tuple_space() {
+                    let (source, payload) = Repository::split_command(payload);
+                    let (destination, payload) = Repository::split_command(payload);
+                    let names = (
+                        std::str::from_utf8(source),
+                        std::str::from_utf8(destination),
+                    );
+                    let template = session.encoding().decode_tuples(payload);
+                    match (names, template) {
+                        ((Ok(source), Ok(destination)), Ok(mut tuples)) if tuples.len() == 1 => {
+                            self.transfer(
+                                client,
+                                session.attributes(),
+                                source,
+                                destination,
+                                tuples.remove(0),
+                                command == MOVE,
+                            )
+                        }
+                        _ => NoResponse(String::from(INVALID_REQUEST)),
+                    }
+                } else {
+                    NoResponse(String::from(NO_TUPLE_SPACE_ATTACHED))
+                }
+            }
+            PROVENANCE => {
+                let (attribute, payload) = Repository::split_command(payload);
+                let (name, payload) = Repository::split_command(payload);
+                let names = (std::str::from_utf8(attribute), std::str::from_utf8(name));
+                match (names, session.encoding().decode_tuples(payload)) {
+                    ((Ok(attribute), Ok(name)), Ok(mut tuples)) if tuples.len() == 1 => {
+                        // Only the administrators may see who wrote the tuples.
+                        let attributes = self.claimed_attributes(session, &[attribute]);
+                        if !attributes.is_some_and(|attributes| {
+                            self.check_claimed_permission(
+                                CREATE,
+                                &attributes,
+                                session.attributes(),
+                                None,
+                            )
+                        }) {
+                            return NoResponse(String::from(NO_PERMISSION));
+                        }
+                        match self.tuple_provenance(name, &tuples.remove(0)) {
+                            None => NoResponse(String::from(TUPLE_SPACE_NOT_FOUND)),
+                            Some(tuples) if tuples.is_empty() => {
+                                NoResponse(String::from(NO_MATCHING_TUPLE_FOUND))
+                            }
+                            Some(tuples) => DataResponse(tuples),
+                        }
+                    }
+                    _ => NoResponse(String::from(INVALID_REQUEST)),
+                }
+            }
+            TRANSACTION => {
+                if let Some(client) = session.tuple_space() {
+                    match Repository::split_operations(session.encoding(), payload) {
+                        Ok(operations) => {
+                            let response =
+                                self.run_transaction(client, session.attributes(), operations);
+                            self.release_parked(client.tuple_space_name(), client.tuple_space());
+                            response
+                        }
+                        Err(_) => NoResponse(String::from(INVALID_REQUEST)),
+                    }
+                } else {
+                    NoResponse(String::from(NO_TUPLE_SPACE_ATTACHED))
+                }
+            }
+            _ => {
+                // The other commands only take words as parameters, whatever the encoding.
+                let request = match std::str::from_utf8(request) {
+                    Ok(request) => request,
+                    Err(_) => return NoResponse(String::from(INVALID_REQUEST)),
+                };
+                let words: Vec<&str> = request.split_whitespace().collect();
+                // The attribute given to the administration commands must be authenticated too.
+                if matches!(
+                    words[0],
+                    CREATE
+                        | DELETE
+                        | DELETE_NAMESPACE
+                        | EXPORT
+                        | IMPORT
+                        | SET_DESCRIPTION
+                        | AUDIT
+                        | GRANT
+                ) && words.get(1).is_some_and(|attribute| {
+                    let attribute = attribute.replace('\"', "");
+                    self.claimed_attributes(session, &[&attribute]).is_none()
+                }) {
+                    return NoResponse(String::from(NO_PERMISSION));
+                }
+                match words[0] {
+                    AUTH => match words.get(1).and_then(|token| self.authenticate(token)) {
+                        Some((attributes, expiration)) => AuthResponse(attributes, expiration),
+                        None => NoResponse(String::from(INVALID_CREDENTIAL)),
+                    },
+                    CREATE | DELETE if words.len() < 3 => NoResponse(String::from(INVALID_REQUEST)),
+                    CREATE => {
+                        let attribute_to_create = String::from(words[1]).replace('\"', "");
+                        if self.check_claimed_permission(
+                            CREATE,
+                            &[attribute_to_create],
+                            session.attributes(),
+                            None,
                         ) {
-                            let param_list = words[1..].join(" ");
-                            let mut tuples: Vec<Tuple> = Lexer::new(&param_list).collect();
-                            let mut response: RequestResponse = NoResponse(String::from(""));
-                            let mut tuple_list: String = String::new();
-                            let mut nb_tuples = 0;
-                            for i in (0..tuples.len()).rev() {
-                                let rd_tup: Tuple = tuples.remove(i);
-                                if !rd_tup.is_empty() {
-                                    let mut space = client.tuple_space().lock().unwrap();
-                                    if let Some(match_tup) =
-                                        executor::block_on(space.tuple_rd(rd_tup))
-                                    {
-                                        if match_tup.is_empty() {
-                                            response =
-                                                NoResponse(String::from(NO_MATCHING_TUPLE_FOUND));
-                                        } else {
-                                            println!("reading tuples {} from space", match_tup);
-                                            tuple_list += &*match_tup.to_string();
-                                            nb_tuples += 1;
-                                            if i != 0 {
-                                                tuple_list.push_str(", ");
-                                            }
+                            if !namespace::is_valid_name(words[2]) {
+                                return NoResponse(String::from(INVALID_TUPLE_SPACE_NAME));
+                            }
+                            let (options, attributes_list) =
+                                Repository::split_word_options(&words[3..]);
+                            let (store_options, limit_options): (Vec<&str>, Vec<&str>) = options
+                                .into_iter()
+                                .partition(|option| StoreKind::is_option(option));
+                            match (
+                                StoreKind::from_options(&store_options, words[2]),
+                                SpaceLimits::from_options(&limit_options),
+                            ) {
+                                (Some(store), Some(limits)) => {
+                                    let metadata = SpaceMetadata::new(
+                                        Some(String::from(words[1])),
+                                        String::new(),
+                                        store,
+                                        limits,
+                                    );
+                                    match self.add_tuple_space_with_metadata(
+                                        String::from(words[2]),
+                                        attributes_list.into_iter().map(String::from).collect(),
+                                        metadata,
+                                    ) {
+                                        Ok(_) => OkResponse(),
+                                        Err(error) => {
+                                            eprintln!("Cannot open the store: {}", error);
+                                            NoResponse(String::from(STORE_UNAVAILABLE))
                                         }
                                     }
-                                } else {
-                                    response = NoResponse(String::from(TUPLE_IS_EMPTY));
-                                }
-                            }
-                            if tuple_list.eq(&String::from("(")) {
-                                response
-                            } else {
-                                if nb_tuples > 1 {
-                                    DataResponse("(".to_owned() + &tuple_list + ")")
-                                } else {
-                                    DataResponse(tuple_list)
                                 }
+                                _ => NoResponse(String::from(INVALID_OPTION)),
                             }
                         } else {
                             NoResponse(String::from(NO_PERMISSION))
                         }
-                    } else {
-                        NoResponse(String::from(NO_TUPLE_SPACE_ATTACHED))
                     }
-                }
-                IN => {
-                    if let Some(client) = client_option {
-                        if self.check_permission(
-                            IN,
-                            client.attributes(),
-                            Some(client.tuple_space_name()),
+                    DELETE => {
+                        let attribute_to_delete = String::from(words[1]);
+                        if self.check_claimed_permission(
+                            DELETE,
+                            &[attribute_to_delete],
+                            session.attributes(),
+                            Some(words[2]),
                         ) {
-                            let param_list = words[1..].join(" ");
-                            let mut tuples: Vec<Tuple> = Lexer::new(&param_list).collect();
-                            let mut response: RequestResponse = NoResponse(String::from(""));
-                            let mut tuple_list: String = String::new();
-                            let mut nb_tuples = 0;
-                            for i in (0..tuples.len()).rev() {
-                                let rd_tup: Tuple = tuples.remove(i);
-                                if !rd_tup.is_empty() {
-                                    let mut space = client.tuple_space().lock().unwrap();
-                                    println!("pulling in tuple matching {} from space", &rd_tup);
-                                    if let Some(match_tup) =
-                                        executor::block_on(space.tuple_in(rd_tup))
+                            self.remove_tuple_space(words[2]);
+                            OkResponse()
+                        } else {
+                            NoResponse(String::from(NO_PERMISSION))
+                        }
+                    }
+                    DELETE_NAMESPACE if words.len() < 3 => {
+                        NoResponse(String::from(INVALID_REQUEST))
+                    }
+                    DELETE_NAMESPACE => {
+                        let attributes = vec![String::from(words[1])];
+                        let names = self.namespace_tuple_spaces(words[2]);
+                        if names.is_empty() {
+                            NoResponse(String::from(TUPLE_SPACE_NOT_FOUND))
+                        } else if names.iter().all(|name| {
+                            self.check_claimed_permission(
+                                DELETE,
+                                &attributes,
+                                session.attributes(),
+                                Some(name),
+                            )
+                        }) {
+                            self.remove_tuple_spaces(&names);
+                            OkResponse()
+                        } else {
+                            NoResponse(String::from(NO_PERMISSION))
+                        }
+                    }
+                    ATTACH if words.len() < 2 => NoResponse(String::from(INVALID_REQUEST)),
+                    ATTACH => {
+                        let tuple_space_found =
+                            self.tuple_spaces.read().unwrap().get(words[1]).cloned();
+                        let attributes_list = self.claimed_attributes(session, &words[2..]);
+                        match (tuple_space_found, attributes_list) {
+                            (None, _) => NoResponse(String::from(TUPLE_SPACE_NOT_FOUND)),
+                            (Some(tuple_space_ref), Some(attributes_list))
+                                if !self
+                                    .permissions(&attributes_list, session.attributes(), words[1])
+                                    .is_empty() =>
+                            {
+                                SpaceResponse(TupleSpace::new(
+                                    tuple_space_ref,
+                                    attributes_list,
+                                    words[1],
+                                ))
+                            }
+                            _ => NoResponse(String::from(NO_PERMISSION)),
+                        }
+                    }
+                    EXPORT | IMPORT if words.len() < 4 => NoResponse(String::from(INVALID_REQUEST)),
+                    EXPORT | IMPORT => {
+                        let admin_attribute = String::from(words[1]);
+                        if !self.check_claimed_permission(
+                            CREATE,
+                            &[admin_attribute],
+                            session.attributes(),
+                            None,
+                        ) {
+                            return NoResponse(String::from(NO_PERMISSION));
+                        }
+                        let (path, format, attributes) =
+                            match Repository::dump_parameters(&words[3..]) {
+                                Ok(parameters) => parameters,
+                                Err(error) => return NoResponse(String::from(error)),
+                            };
+                        let exists = self.tuple_spaces.read().unwrap().contains_key(words[2]);
+                        let result = match (words[0], exists) {
+                            (EXPORT, false) => Err(TUPLE_SPACE_NOT_FOUND),
+                            (EXPORT, true) => self.export_tuple_space(words[2], &path, format),
+                            (_, true) => self.import_tuple_space(words[2], &path, format),
+                            (_, false) if !namespace::is_valid_name(words[2]) => {
+                                Err(INVALID_TUPLE_SPACE_NAME)
+                            }
+                            // Imports into a new tuple space, created with the attributes and
+                            // removed if the import fails.
+                            (_, false) => self
+                                .add_tuple_space_with_metadata(
+                                    String::from(words[2]),
+                                    attributes.into_iter().map(String::from).collect(),
+                                    SpaceMetadata::new(
+                                        Some(String::from(words[1])),
+                                        String::new(),
+                                        StoreKind::default(),
+                                        SpaceLimits::unlimited(),
+                                    ),
+                                )
+                                .map_err(|error| {
+                                    eprintln!("Cannot open the store: {}", error);
+                                    STORE_UNAVAILABLE
+                                })
+                                .and_then(|_| self.import_tuple_space(words[2], &path, format))
+                                .inspect_err(|_| self.remove_tuple_space(words[2])),
+                        };
+                        match result {
+                            Ok(count) => {
+                                println!("{} {} tuples of {}", words[0], count, words[2]);
+                                OkResponse()
+                            }
+                            Err(error) => NoResponse(String::from(error)),
+                        }
+                    }
+                    GRANT if words.len() < 5 => NoResponse(String::from(INVALID_REQUEST)),
+                    GRANT => {
+                        let (tuple_space_name, action, attribute) = (words[2], words[3], words[4]);
+                        if !namespace::is_valid_name(tuple_space_name) {
+                            return NoResponse(String::from(INVALID_TUPLE_SPACE_NAME));
+                        }
+                        if ![READ, IN, OUT, DELETE].contains(&action) {
+                            return NoResponse(String::from(INVALID_REQUEST));
+                        }
+                        let mut expiration = None;
+                        let mut delegable = false;
+                        for option in &words[5..] {
+                            match option.split_once('=') {
+                                Some((TTL, value)) => {
+                                    match value
+                                        .parse::<f64>()
+                                        .ok()
+                                        .and_then(|value| Duration::try_from_secs_f64(value).ok())
+                                        .and_then(|ttl| SystemTime::now().checked_add(ttl))
                                     {
-                                        if match_tup.is_empty() {
-                                            response =
-                                                NoResponse(String::from(NO_MATCHING_TUPLE_FOUND));
-                                        } else {
-                                            tuple_list += &*match_tup.to_string();
-                                            nb_tuples += 1;
-                                            if i != 0 {
-                                                tuple_list.push_str(", ");
-                                            }
-                                        }
+                                        Some(value) => expiration = Some(value),
+                                        None => return NoResponse(String::from(INVALID_OPTION)),
                                     }
-                                } else {
-                                    response = NoResponse(String::from(TUPLE_IS_EMPTY));
                                 }
+                                None if *option == DELEGABLE => delegable = true,
+                                _ => return NoResponse(String::from(INVALID_OPTION)),
                             }
-                            if tuple_list.is_empty() {
-                                response
-                            } else {
-                                if nb_tuples > 1 {
-                                    DataResponse("(".to_owned() + &tuple_list + ")")
-                                } else {
-                                    DataResponse(tuple_list)
+                        }
+                        // The administrators grant anything, the others only delegate their grants.
+                        let grantor = [String::from(words[1]).replace('\"', "")];
+                        if self.check_claimed_permission(
+                            CREATE,
+                            &grantor,
+                            session.attributes(),
+                            None,
+                        ) {
+                            self.grant(tuple_space_name, action, attribute, expiration, delegable);
+                            return OkResponse();
+                        }
+                        match self.delegate(
+                            &grantor,
+                            tuple_space_name,
+                            action,
+                            attribute,
+                            expiration,
+                            delegable,
+                        ) {
+                            Ok(_) => OkResponse(),
+                            Err(error) => NoResponse(error),
+                        }
+                    }
+                    AUDIT if words.len() < 2 => NoResponse(String::from(INVALID_REQUEST)),
+                    AUDIT => {
+                        if !self.check_claimed_permission(
+                            CREATE,
+                            &[String::from(words[1])],
+                            session.attributes(),
+                            None,
+                        ) {
+                            return NoResponse(String::from(NO_PERMISSION));
+                        }
+                        let mut filters = Vec::new();
+                        let mut since = 0.0;
+                        let mut limit = None;
+                        for option in &words[2..] {
+                            match option.split_once('=') {
+                                Some((SINCE, value)) => match value.parse::<f64>() {
+                                    Ok(value) => since = value,
+                                    Err(_) => return NoResponse(String::from(INVALID_OPTION)),
+                                },
+                                Some((LIMIT, value)) => match value.parse::<usize>() {
+                                    Ok(value) => limit = Some(value),
+                                    Err(_) => return NoResponse(String::from(INVALID_OPTION)),
+                                },
+                                Some((
+                                    name @ (TUPLE_SPACE | COMMAND | ATTRIBUTE | PEER | RESULT),
+                                    value,
+                                )) => filters.push((name, value)),
+                                _ => return NoResponse(String::from(INVALID_OPTION)),
+                            }
+                        }
+                        let records = self.audit_records(
+                            |record| {
+                                record.timestamp >= since
+                                    && filters.iter().all(|(name, value)| match *name {
+                                        TUPLE_SPACE => record.tuple_space.as_deref() == Some(value),
+                                        COMMAND => record.command == *value,
+                                        ATTRIBUTE => record
+                                            .attributes
+                                            .iter()
+                                            .any(|attribute| attribute == value),
+                                        PEER => record.peer.as_deref() == Some(value),
+                                        _ => record.result == *value,
+                                    })
+                            },
+                            limit,
+                        );
+                        match records {
+                            Ok((records, malformed)) => {
+                                if malformed > 0 {
+                                    eprintln!(
+                                        "Skipped {} malformed lines of the audit log",
+                                        malformed
+                                    );
                                 }
+                                DataResponse(records.iter().map(AuditRecord::to_tuple).collect())
+                            }
+                            Err(error) => {
+                                eprintln!("Cannot read the audit log: {}", error);
+                                NoResponse(String::from(AUDIT_UNAVAILABLE))
+                            }
+                        }
+                    }
+                    METADATA if words.len() < 2 => NoResponse(String::from(INVALID_REQUEST)),
+                    METADATA => {
+                        let attributes = match self.request_attributes(session, &words[2..]) {
+                            Some(attributes) => attributes,
+                            None => return NoResponse(String::from(NO_PERMISSION)),
+                        };
+                        match self.tuple_space_metadata(words[1]) {
+                            Some(metadata)
+                                if !self
+                                    .permissions(&attributes, session.attributes(), words[1])
+                                    .is_empty() =>
+                            {
+                                DataResponse(vec![metadata.to_tuple(words[1])])
+                            }
+                            _ => NoResponse(String::from(TUPLE_SPACE_NOT_FOUND)),
+                        }
+                    }
+                    SET_DESCRIPTION if words.len() < 3 => NoResponse(String::from(INVALID_REQUEST)),
+                    SET_DESCRIPTION => {
+                        let attribute = String::from(words[1]);
+                        if self.check_claimed_permission(
+                            DELETE,
+                            &[attribute],
+                            session.attributes(),
+                            Some(words[2]),
+                        ) {
+                            let description = words[3..].join(SPACE);
+                            if self.set_tuple_space_description(words[2], description) {
+                                OkResponse()
+                            } else {
+                                NoResponse(String::from(TUPLE_SPACE_NOT_FOUND))
                             }
                         } else {
                             NoResponse(String::from(NO_PERMISSION))
                         }
-                    } else {
-                        NoResponse(String::from(NO_TUPLE_SPACE_ATTACHED))
                     }
+                    LIST => {
+                        let (options, attributes) = Repository::split_word_options(&words[1..]);
+                        let mut prefix = None;
+                        for option in options {
+                            match option.split_once('=') {
+                                Some((PREFIX, namespace)) => prefix = Some(namespace),
+                                _ => return NoResponse(String::from(INVALID_OPTION)),
+                            }
+                        }
+                        let attributes = match self.request_attributes(session, &attributes) {
+                            Some(attributes) => attributes,
+                            None => return NoResponse(String::from(NO_PERMISSION)),
+                        };
+                        DataResponse(self.describe(
+                            &attributes,
+                            session.attributes(),
+                            |name| {
+                                prefix.is_none_or(|prefix| namespace::is_in_namespace(name, prefix))
+                            },
+                            false,
+                        ))
+                    }
+                    DESCRIBE => match words.get(1) {
+                        Some(tuple_space_name) => {
+                            let attributes = match self.request_attributes(session, &words[2..]) {
+                                Some(attributes) => attributes,
+                                None => return NoResponse(String::from(NO_PERMISSION)),
+                            };
+                            let mut description = self.describe(
+                                &attributes,
+                                session.attributes(),
+                                |name| name == *tuple_space_name,
+                                true,
+                            );
+                            match description.pop() {
+                                Some(description) => DataResponse(vec![description]),
+                                None => NoResponse(String::from(TUPLE_SPACE_NOT_FOUND)),
+                            }
+                        }
+                        None => NoResponse(String::from(INVALID_REQUEST)),
+                    },
+                    ENCODING => match words.get(1).and_then(|name| Encoding::from_name(name)) {
+                        Some(encoding) => EncodingResponse(encoding),
+                        None => NoResponse(String::from(UNKNOWN_ENCODING)),
+                    },
+                    _ => NoResponse(String::from(REQUEST_DOESNT_EXIST)),
                 }
-                _ => NoResponse(String::from(REQUEST_DOESNT_EXIST)),
             }
-        } else {
-            NoResponse(String::from(EMPTY_REQUEST))
         }
     }
 }
@@ -0,0 +1,61 @@
+//! Module Role
+//!
+//! Roles bundling attributes and the hierarchy of attributes. Each role or attribute implies other
+//! attributes, which in turn may imply more, so that `admin` implying `operator` implying `reader`
+//! gives every permission of `reader` and `operator` to the holders of `admin`. The permissions are
+//! checked against all the attributes implied by those of a client.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+
+#[derive(Default)]
+pub struct Roles {
+    implications: RwLock<HashMap<String, Vec<String>>>,
+}
+
+impl Roles {
+    /// Makes the role imply the attributes, in addition to those it already implies.
+    pub fn add(&self, role: &str, attributes: Vec<String>) {
+        let mut implications = self.implications.write().unwrap();
+        let implied = implications.entry(String::from(role)).or_default();
+        for attribute in attributes {
+            if !implied.contains(&attribute) {
+                implied.push(attribute);
+            }
+        }
+    }
+
+    /// Returns `false` if the role implies nothing.
+    pub fn remove(&self, role: &str) -> bool {
+        self.implications.write().unwrap().remove(role).is_some()
+    }
+
+    /// Returns the attributes implied by the role, directly.
+    pub fn get(&self, role: &str) -> Vec<String> {
+        self.implications
+            .read()
+            .unwrap()
+            .get(role)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Returns the attributes followed by every attribute they imply, each once. Cycles in the
+    /// hierarchy are allowed.
+    pub fn expand(&self, attributes: &[String]) -> Vec<String> {
+        let implications = self.implications.read().unwrap();
+        let mut seen: HashSet<&str> = HashSet::new();
+        let mut expanded: Vec<String> = Vec::with_capacity(attributes.len());
+        let mut pending: Vec<&str> = attributes.iter().map(String::as_str).rev().collect();
+        while let Some(attribute) = pending.pop() {
+            if !seen.insert(attribute) {
+                continue;
+            }
+            expanded.push(String::from(attribute));
+            if let Some(implied) = implications.get(attribute) {
+                pending.extend(implied.iter().map(String::as_str).rev());
+            }
+        }
+        expanded
+    }
+}
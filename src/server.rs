@@ -1,4 +1,9 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
 use crate::{tcp_server, udp_server};
+use crate::constant::EXPIRATION_SWEEP_INTERVAL;
 use crate::repository::Repository;
 
 pub enum Protocol {
@@ -31,7 +36,24 @@ impl Server<'_> {
         }
     }
 
+    /// Serves the requests until the server fails, sweeping the expired tuples of the repository
+    /// in the background meanwhile.
     pub fn start_server(&self) -> std::io::Result<()> {
+        let stopped = AtomicBool::new(false);
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                while !stopped.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(EXPIRATION_SWEEP_INTERVAL));
+                    self.repository.sweep_expirations(Instant::now());
+                }
+            });
+            let result = self.serve();
+            stopped.store(true, Ordering::Relaxed);
+            result
+        })
+    }
+
+    fn serve(&self) -> std::io::Result<()> {
         match &self.protocol {
             Protocol::TCP => {
                 tcp_server::launch_server(&self.ip_address, &self.port, &self.repository,&self.key)
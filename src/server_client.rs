@@ -1,38 +1,63 @@
+use crate::codec::Encoding;
 use crate::constant;
-use crate::constant::{STOP_SERVER, TIMEOUT};
+use crate::constant::{
+    CONNECTED, MAX_UDP_PAYLOAD, NOTIFY, STOP_SERVER, TIMEOUT, UNREADABLE_RESPONSE,
+};
 use constant::{TCP, UDP};
-use std::io::{BufRead, Write};
-use std::net::{Ipv4Addr, TcpStream, UdpSocket};
+use rustupolis::tuple::Tuple;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, Shutdown, TcpStream, UdpSocket};
 use std::str::{from_utf8};
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc, Mutex};
 use std::sync::mpsc::{Receiver, Sender, TryRecvError};
 use std::time::Duration;
-use std::{io, thread};
+use std::thread;
 use aes_gcm::{Aes128Gcm, Key, Nonce}; // Or `Aes128Gcm`
 use aes_gcm::aead::{Aead, NewAead};
 
+/// The callbacks of the subscriptions to a server, with the encoding of their notifications.
+type Callbacks = Arc<Mutex<HashMap<u64, (Encoding, Box<dyn FnMut(Tuple) + Send>)>>>;
+
 pub struct ServerClient {
-    mpsc_channel: (Sender<String>, Receiver<String>),
+    mpsc_channel: (Sender<Vec<u8>>, Receiver<Vec<u8>>),
+    encoding: Encoding,
+    callbacks: Callbacks,
+    next_subscription: u64,
 }
 
 impl ServerClient {
     pub fn new(ip_address: String, port: String, protocol: String, server_name:String, key: &str) -> ServerClient {
         let address = format!("{}:{}", ip_address, port);
         let addr: String = address.parse().unwrap();
-        let (tx, rx): (Sender<String>, Receiver<String>) = mpsc::channel();
-        let (tx_response, rx_response): (Sender<String>, Receiver<String>) = mpsc::channel();
+        let (tx, rx): (Sender<Vec<u8>>, Receiver<Vec<u8>>) = mpsc::channel();
+        let (tx_response, rx_response): (Sender<Vec<u8>>, Receiver<Vec<u8>>) = mpsc::channel();
         let key_string = String::from(key);
+        let callbacks = Callbacks::default();
+        let notification_callbacks = callbacks.clone();
 
         match protocol.as_str() {
             TCP => {
                 match thread::Builder::new().name(server_name).spawn(move || {
                     match TcpStream::connect(addr) {
                         Ok(mut server) => {
-                            ServerClient::read_response_tcp(&mut server, &tx_response,&key_string);
+                            ServerClient::read_connected_tcp(&mut server, &tx_response);
+                            // Responses and notifications are read by another thread, as the
+                            // server may push notifications at any time.
+                            match server.try_clone() {
+                                Ok(reader) => {
+                                    let key = key_string.clone();
+                                    thread::spawn(move || {
+                                        ServerClient::read_messages_tcp(reader, &tx_response, &notification_callbacks, &key)
+                                    });
+                                }
+                                Err(error) => eprintln!("{}", error),
+                            }
                             loop {
-                                match &rx.try_recv() {
+                                match &rx.recv() {
                                     Ok(message) => {
-                                        if STOP_SERVER.eq(message.as_str()) {
+                                        if STOP_SERVER.as_bytes() == message.as_slice() {
+                                            let _ = server.shutdown(Shutdown::Both);
                                             break;
                                         }
                                         let key_gen = Key::from_slice(key_string.as_bytes());
@@ -50,13 +75,11 @@ impl ServerClient {
                                                 eprintln!("{}", e)
                                             }
                                         };
-                                        ServerClient::read_response_tcp(&mut server, &tx_response,&key_string);
                                     }
-                                    Err(TryRecvError::Disconnected) => {
+                                    Err(_) => {
                                         println!("Terminating.");
                                         break;
                                     }
-                                    Err(_) => {}
                                 };
                             }
                         }
@@ -79,7 +102,7 @@ impl ServerClient {
                                 Ok(_) => loop {
                                     match &rx.try_recv() {
                                         Ok(message) => {
-                                            if STOP_SERVER.eq(message.as_str()) {
+                                            if STOP_SERVER.as_bytes() == message.as_slice() {
                                                 break;
                                             }
 
@@ -98,7 +121,14 @@ impl ServerClient {
                                                     eprintln!("{}", e)
                                                 }
                                             };
-                                            ServerClient::read_response_udp(&mut server, &tx_response, &key_string);
+                                            let response = ServerClient::read_response_udp(&mut server, &key_string)
+                                                .unwrap_or_else(|error| {
+                                                    eprintln!("{}", error);
+                                                    Vec::from(UNREADABLE_RESPONSE)
+                                                });
+                                            if let Err(error) = tx_response.send(response) {
+                                                eprintln!("{}", error)
+                                            }
                                         }
                                         Err(TryRecvError::Disconnected) => {
                                             println!("Terminating.");
@@ -129,7 +159,7 @@ impl ServerClient {
         loop {
             match rx_response.recv_timeout(Duration::from_secs(TIMEOUT)) {
                 Ok(response) => {
-                    println!("{}", response);
+                    println!("{}", String::from_utf8_lossy(&response));
                     break;
                 }
                 Err(_) => {
@@ -140,78 +170,111 @@ impl ServerClient {
 
         ServerClient {
             mpsc_channel: (tx, rx_response),
+            encoding: Encoding::Text,
+            callbacks,
+            next_subscription: 0,
         }
     }
 
-    pub fn read_response_tcp(server: &mut TcpStream, tx_response: &Sender<String>,key: &String) {
-        let mut reader = io::BufReader::new(server);
-        loop {
-            match reader.fill_buf() {
-                Ok(response) => {
-                    if response.len() != 0 {
-                        match from_utf8(&response) {
-                            Ok(text) => {
-                                if text == "Connected" {
-                                    match tx_response.send(String::from(text)) {
-                                        Ok(_) => {}
-                                        Err(error) => {eprintln!("{}",error)}
-                                    };
-                                    break;
-                                }
-                            }
-                            Err(_) => {
-                                let key = Key::from_slice(key.as_ref());
-                                let cipher = Aes128Gcm::new(key);
+    pub fn read_connected_tcp(server: &mut TcpStream, tx_response: &Sender<Vec<u8>>) {
+        let mut connected = vec![0; CONNECTED.len()];
+        match server.read_exact(&mut connected) {
+            Ok(_) => match tx_response.send(connected) {
+                Ok(_) => {}
+                Err(error) => {eprintln!("{}",error)}
+            },
+            Err(error) => eprintln!("{}", error),
+        }
+    }
 
-                                let nonce = Nonce::from_slice(b"unique nonce"); // 96-bits; unique per message
-                                let text = cipher.decrypt(nonce, response).expect("decryption failure!");
+    /// Reads the messages of the server, each one prefixed by its length, until the connection
+    /// is closed. Notifications are handed to the callbacks of their subscription, and the other
+    /// messages are responses.
+    pub fn read_messages_tcp(mut server: TcpStream, tx_response: &Sender<Vec<u8>>, callbacks: &Callbacks, key: &String) {
+        let mut length = [0; 4];
+        while server.read_exact(&mut length).is_ok() {
+            let mut response = vec![0; u32::from_be_bytes(length) as usize];
+            if let Err(error) = server.read_exact(&mut response) {
+                eprintln!("{}", error);
+                break;
+            }
+            let key = Key::from_slice(key.as_ref());
+            let cipher = Aes128Gcm::new(key);
 
-                                match tx_response.send(String::from_utf8(text).unwrap()) {
-                                    Ok(_) => {}
-                                    Err(error) => {eprintln!("{}",error)}
-                                };
-                                break;
-                            }
-                        };
+            let nonce = Nonce::from_slice(b"unique nonce"); // 96-bits; unique per message
+            let text = cipher.decrypt(nonce, response.as_ref()).expect("decryption failure!");
+
+            match ServerClient::split_notification(&text) {
+                Some((id, payload)) => {
+                    if let Some((encoding, callback)) = callbacks.lock().unwrap().get_mut(&id) {
+                        match encoding.decode_tuples(payload) {
+                            Ok(tuples) => tuples.into_iter().for_each(callback),
+                            Err(error) => eprintln!("{}", error),
+                        }
                     }
                 }
-                Err(error) => {
-                    eprintln!("{}", error)
-                }
+                None => match tx_response.send(text) {
+                    Ok(_) => {}
+                    Err(error) => {eprintln!("{}",error)}
+                },
             }
         }
     }
 
-    pub fn read_response_udp(server: &mut UdpSocket, tx_response: &Sender<String>, key: &String) {
-        let mut buf = [0; 64];
+    /// Splits a `notify {id} {tuple}` message into the id of the subscription and the tuple.
+    fn split_notification(message: &[u8]) -> Option<(u64, &[u8])> {
+        let message = message.strip_prefix(NOTIFY.as_bytes())?.strip_prefix(b" ")?;
+        let end = message.iter().position(|byte| *byte == b' ')?;
+        let id = from_utf8(&message[..end]).ok()?.parse().ok()?;
+        Some((id, &message[end + 1..]))
+    }
+
+    /// Reads the response to a request, which may take a whole datagram. Returns an error if it
+    /// cannot be received or decrypted.
+    pub fn read_response_udp(server: &mut UdpSocket, key: &String) -> Result<Vec<u8>, String> {
+        let mut buf = vec![0; MAX_UDP_PAYLOAD];
         loop {
-            match server.recv(&mut buf) {
-                Ok(response) => {
-                    if response != 0 {
-                        //let text = from_utf8(&buf).unwrap().trim_matches(char::from(0));
-                        let key = Key::from_slice(key.as_ref());
-                        let cipher = Aes128Gcm::new(key);
-
-                        let nonce = Nonce::from_slice(b"unique nonce"); // 96-bits; unique per message
-                        let text = cipher.decrypt(nonce, &buf[..response]).expect("decryption failure!");
-
-                        match tx_response.send(String::from_utf8(text).unwrap()) {
-                            Ok(_) => {}
-                            Err(error) => {eprintln!("{}",error)}
-                        };
-                        break;
-                    }
-                }
-                Err(error) => {
-                    eprintln!("{}", error)
-                }
+            let response = server.recv(&mut buf).map_err(|error| error.to_string())?;
+            if response != 0 {
+                let key = Key::from_slice(key.as_ref());
+                let cipher = Aes128Gcm::new(key);
+
+                let nonce = Nonce::from_slice(b"unique nonce"); // 96-bits; unique per message
+                return cipher
+                    .decrypt(nonce, &buf[..response])
+                    .map_err(|_| String::from("cannot decrypt the response"));
             }
         }
     }
 
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
+    /// Registers the callback of a new subscription, whose notifications are encoded with the
+    /// current encoding. Returns the id of the subscription.
+    pub fn add_callback(&mut self, callback: Box<dyn FnMut(Tuple) + Send>) -> u64 {
+        let id = self.next_subscription;
+        self.next_subscription += 1;
+        self.callbacks.lock().unwrap().insert(id, (self.encoding, callback));
+        id
+    }
+
+    pub fn remove_callback(&self, id: u64) {
+        self.callbacks.lock().unwrap().remove(&id);
+    }
+
     pub fn send_message(&self, message: String) -> String {
+        String::from_utf8_lossy(&self.send_bytes(message.into_bytes())).into_owned()
+    }
+
+    pub fn send_bytes(&self, message: Vec<u8>) -> Vec<u8> {
         let (tx, rx_response) = &self.mpsc_channel;
-        println!("Sending :{}",message);
+        println!("Sending :{}", String::from_utf8_lossy(&message));
         match tx.send(message) {
             Ok(_) => {}
             Err(error) => {
@@ -222,7 +285,7 @@ impl ServerClient {
         loop {
             match rx_response.recv_timeout(Duration::from_secs(TIMEOUT)) {
                 Ok(response) => {
-                    println!("Response : {}",response);
+                    println!("Response : {}", String::from_utf8_lossy(&response));
                     return response;
                 }
                 Err(_) => {}
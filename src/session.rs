@@ -0,0 +1,120 @@
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use crate::codec::Encoding;
+use crate::constant::{TCP, UDP};
+use crate::subscription::Notifier;
+use crate::tuple_space::TupleSpace;
+
+static NEXT_SESSION_ID: AtomicU64 = AtomicU64::new(0);
+
+/// State kept by a server for each of its clients.
+pub struct Session {
+    id: u64,
+    protocol: Option<&'static str>,
+    peer: Option<SocketAddr>,
+    encoding: Encoding,
+    tuple_space: Option<TupleSpace>,
+    notifier: Option<Notifier>,
+    attributes: Vec<String>,
+    expiration: Option<SystemTime>,
+}
+
+impl Default for Session {
+    fn default() -> Session {
+        Session {
+            id: NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed),
+            protocol: None,
+            peer: None,
+            encoding: Encoding::default(),
+            tuple_space: None,
+            notifier: None,
+            attributes: Vec::new(),
+            expiration: None,
+        }
+    }
+}
+
+impl Session {
+    pub fn new() -> Session {
+        Session::default()
+    }
+
+    /// Creates the session of a TCP client, which can receive notifications.
+    pub fn with_notifier(notifier: Notifier, peer: SocketAddr) -> Session {
+        Session {
+            protocol: Some(TCP),
+            peer: Some(peer),
+            notifier: Some(notifier),
+            ..Session::default()
+        }
+    }
+
+    /// Creates the session of a UDP client.
+    pub fn with_peer(peer: SocketAddr) -> Session {
+        Session {
+            protocol: Some(UDP),
+            peer: Some(peer),
+            ..Session::default()
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn protocol(&self) -> Option<&'static str> {
+        self.protocol
+    }
+
+    pub fn peer(&self) -> Option<SocketAddr> {
+        self.peer
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+    }
+
+    pub fn tuple_space(&self) -> Option<&TupleSpace> {
+        self.tuple_space.as_ref()
+    }
+
+    /// Attaches the session to a tuple space. Returns `true` if it replaced a previous attachment.
+    pub fn attach(&mut self, tuple_space: TupleSpace) -> bool {
+        self.tuple_space.replace(tuple_space).is_some()
+    }
+
+    pub fn notifier(&self) -> Option<&Notifier> {
+        self.notifier.as_ref()
+    }
+
+    /// The attributes the client proved to hold, which are the only ones its requests may claim.
+    /// There are none once the credential expired.
+    pub fn attributes(&self) -> &[String] {
+        match self.is_expired() {
+            true => &[],
+            false => &self.attributes,
+        }
+    }
+
+    /// Replaces the attributes of the session with those of a credential, valid until the
+    /// expiration if any.
+    pub fn authenticate(&mut self, attributes: Vec<String>, expiration: Option<SystemTime>) {
+        self.attributes = attributes;
+        self.expiration = expiration;
+    }
+
+    pub fn expiration(&self) -> Option<SystemTime> {
+        self.expiration
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expiration
+            .is_some_and(|expiration| expiration <= SystemTime::now())
+    }
+}
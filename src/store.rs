@@ -0,0 +1,352 @@
+//! Module Store
+//!
+//! Backends holding the tuples of a tuple space. Each tuple space is created with a `StoreKind`,
+//! chosen through `Repository::add_tuple_space` or the options of the `create` command.
+
+use std::collections::{BTreeSet, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use rustupolis::tuple::Tuple;
+
+use crate::constant::{
+    CAPACITY, DISK, DISK_STORE_DIRECTORY, INDEXED, KEY_FIELD, MEMORY, RING, STORE,
+};
+use crate::indexed_store::IndexedStore;
+use crate::lexing::{format_tuple, parse_tuples};
+
+/// Storage of the tuples of a tuple space. Stores only hold defined tuples, and matching a
+/// template returns `None` rather than waiting for a tuple.
+pub trait TupleStore: Send {
+    /// Writes a tuple. Returns the tuple dropped to make room for it, for bounded stores.
+    fn out(&mut self, tuple: Tuple) -> Result<Option<Tuple>, String>;
+    /// Removes and returns a tuple matching the template.
+    fn inp(&mut self, template: &Tuple) -> Option<Tuple>;
+    /// Returns a tuple matching the template.
+    fn rdp(&mut self, template: &Tuple) -> Option<Tuple>;
+    /// Returns every tuple matching the template.
+    fn rdp_all(&self, template: &Tuple) -> Vec<Tuple> {
+        self.tuples()
+            .into_iter()
+            .filter(|tuple| template.matches(tuple))
+            .collect()
+    }
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Returns a copy of every tuple of the store.
+    fn tuples(&self) -> Vec<Tuple>;
+}
+
+/// The backends a tuple space can be created with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StoreKind {
+    /// Tuples in a sorted set, matched like the `SimpleStore` of Rustupolis.
+    Memory,
+    /// Tuples indexed by arity and by the value of the given field.
+    Indexed(usize),
+    /// At most the given number of tuples, the oldest one being dropped to make room.
+    Ring(usize),
+    /// Tuples kept in memory and logged into the given file, which is replayed when opened.
+    Disk(PathBuf),
+}
+
+impl Default for StoreKind {
+    fn default() -> StoreKind {
+        StoreKind::Indexed(0)
+    }
+}
+
+impl StoreKind {
+    /// Names of the options of the `create` command which select the store.
+    pub const OPTIONS: [&'static str; 3] = [STORE, KEY_FIELD, CAPACITY];
+
+    pub fn is_option(option: &str) -> bool {
+        option
+            .split_once('=')
+            .is_some_and(|(name, _)| StoreKind::OPTIONS.contains(&name))
+    }
+
+    /// Reads the store from the `name=value` options of the `create` command. A disk store is
+    /// kept in `DISK_STORE_DIRECTORY`, in a file named after the tuple space.
+    pub fn from_options(options: &[&str], tuple_space_name: &str) -> Option<StoreKind> {
+        let mut store = INDEXED;
+        let mut key_field = 0;
+        let mut capacity = None;
+        for option in options {
+            match option.split_once('=')? {
+                (STORE, value) => store = value,
+                (KEY_FIELD, value) => key_field = value.parse().ok()?,
+                (CAPACITY, value) => capacity = Some(value.parse().ok()?),
+                _ => return None,
+            }
+        }
+        match store {
+            MEMORY => Some(StoreKind::Memory),
+            INDEXED => Some(StoreKind::Indexed(key_field)),
+            RING => Some(StoreKind::Ring(capacity?)),
+            DISK => Some(StoreKind::Disk(
+                Path::new(DISK_STORE_DIRECTORY).join(DiskStore::file_name(tuple_space_name)),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Writes the store as options of the `create` command. The server chooses the file of a
+    /// disk store.
+    pub fn to_options(&self) -> Vec<String> {
+        let mut options = vec![format!("{}={}", STORE, self.name())];
+        match self {
+            StoreKind::Indexed(key_field) => options.push(format!("{}={}", KEY_FIELD, key_field)),
+            StoreKind::Ring(capacity) => options.push(format!("{}={}", CAPACITY, capacity)),
+            StoreKind::Memory | StoreKind::Disk(_) => {}
+        }
+        options
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StoreKind::Memory => MEMORY,
+            StoreKind::Indexed(_) => INDEXED,
+            StoreKind::Ring(_) => RING,
+            StoreKind::Disk(_) => DISK,
+        }
+    }
+
+    /// Creates the store, or opens it for a disk store.
+    pub fn open(&self) -> Result<Box<dyn TupleStore>, String> {
+        Ok(match self {
+            StoreKind::Memory => Box::new(MemoryStore::new()),
+            StoreKind::Indexed(key_field) => Box::new(IndexedStore::new(*key_field)),
+            StoreKind::Ring(capacity) => Box::new(RingStore::new(*capacity)),
+            StoreKind::Disk(path) => Box::new(DiskStore::open(path)?),
+        })
+    }
+}
+
+/// A sorted set of tuples. The tuples compared with a template are narrowed with the same range
+/// as `SimpleStore`, which is only efficient when the first fields of the template are defined.
+#[derive(Default)]
+pub struct MemoryStore {
+    tuples: BTreeSet<Tuple>,
+}
+
+impl MemoryStore {
+    pub fn new() -> MemoryStore {
+        MemoryStore::default()
+    }
+}
+
+impl TupleStore for MemoryStore {
+    fn out(&mut self, tuple: Tuple) -> Result<Option<Tuple>, String> {
+        if !tuple.is_defined() {
+            return Err(String::from("cannot write an undefined tuple"));
+        }
+        self.tuples.insert(tuple);
+        Ok(None)
+    }
+
+    fn inp(&mut self, template: &Tuple) -> Option<Tuple> {
+        let tuple = self.rdp(template)?;
+        self.tuples.take(&tuple)
+    }
+
+    fn rdp(&mut self, template: &Tuple) -> Option<Tuple> {
+        if template.is_defined() {
+            return self.tuples.get(template).cloned();
+        }
+        self.tuples
+            .range(template.range())
+            .find(|tuple| template.matches(tuple))
+            .cloned()
+    }
+
+    fn rdp_all(&self, template: &Tuple) -> Vec<Tuple> {
+        if template.is_defined() {
+            return self.tuples.get(template).into_iter().cloned().collect();
+        }
+        self.tuples
+            .range(template.range())
+            .filter(|tuple| template.matches(tuple))
+            .cloned()
+            .collect()
+    }
+
+    fn len(&self) -> usize {
+        self.tuples.len()
+    }
+
+    fn tuples(&self) -> Vec<Tuple> {
+        self.tuples.iter().cloned().collect()
+    }
+}
+
+/// A bounded buffer of tuples in insertion order, matched by scanning from the oldest one.
+pub struct RingStore {
+    capacity: usize,
+    tuples: VecDeque<Tuple>,
+}
+
+impl RingStore {
+    pub fn new(capacity: usize) -> RingStore {
+        RingStore {
+            capacity,
+            tuples: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn position(&self, template: &Tuple) -> Option<usize> {
+        self.tuples.iter().position(|tuple| template.matches(tuple))
+    }
+}
+
+impl TupleStore for RingStore {
+    fn out(&mut self, tuple: Tuple) -> Result<Option<Tuple>, String> {
+        if !tuple.is_defined() {
+            return Err(String::from("cannot write an undefined tuple"));
+        }
+        if self.capacity == 0 {
+            return Ok(Some(tuple));
+        }
+        let dropped = if self.tuples.len() == self.capacity {
+            self.tuples.pop_front()
+        } else {
+            None
+        };
+        self.tuples.push_back(tuple);
+        Ok(dropped)
+    }
+
+    fn inp(&mut self, template: &Tuple) -> Option<Tuple> {
+        let index = self.position(template)?;
+        self.tuples.remove(index)
+    }
+
+    fn rdp(&mut self, template: &Tuple) -> Option<Tuple> {
+        let index = self.position(template)?;
+        self.tuples.get(index).cloned()
+    }
+
+    fn len(&self) -> usize {
+        self.tuples.len()
+    }
+
+    fn tuples(&self) -> Vec<Tuple> {
+        self.tuples.iter().cloned().collect()
+    }
+}
+
+const LOG_OUT: char = '+';
+const LOG_IN: char = '-';
+
+/// An indexed store whose operations are appended to a log file, one line per operation: `+`
+/// followed by a written tuple or `-` followed by a removed one, in the text grammar. The log is
+/// replayed and compacted when the store is opened.
+pub struct DiskStore {
+    store: IndexedStore,
+    log: File,
+}
+
+impl DiskStore {
+    /// Returns the name of the log of a tuple space in `DISK_STORE_DIRECTORY`, escaping the
+    /// characters of its name which are not allowed in a file name.
+    pub fn file_name(tuple_space_name: &str) -> String {
+        let mut name = String::new();
+        for byte in tuple_space_name.bytes() {
+            if byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-' {
+                name.push(byte as char);
+            } else {
+                name.push_str(&format!("%{:02X}", byte));
+            }
+        }
+        name + ".tuples"
+    }
+
+    pub fn open(path: &Path) -> Result<DiskStore, String> {
+        let mut store = IndexedStore::default();
+        if path.exists() {
+            let log = fs::read_to_string(path).map_err(|error| error.to_string())?;
+            for line in log.lines() {
+                let (tuples, operation) = match line.chars().next() {
+                    Some(LOG_OUT) => (parse_tuples(&line[1..])?, LOG_OUT),
+                    Some(LOG_IN) => (parse_tuples(&line[1..])?, LOG_IN),
+                    _ => return Err(format!("invalid line in {}: {}", path.display(), line)),
+                };
+                for tuple in tuples {
+                    if operation == LOG_OUT {
+                        store.out(tuple)?;
+                    } else {
+                        store.inp(&tuple);
+                    }
+                }
+            }
+        } else if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(|error| error.to_string())?;
+        }
+
+        // Rewrite the log with the remaining tuples only, then append to it. The compacted log is
+        // written next to the log and renamed over it, so that a crash leaves one of them whole.
+        let mut compacted = String::new();
+        for tuple in store.tuples() {
+            compacted.push(LOG_OUT);
+            compacted.push_str(&format_tuple(&tuple));
+            compacted.push('\n');
+        }
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        let temporary = PathBuf::from(temporary);
+        let mut file = File::create(&temporary).map_err(|error| error.to_string())?;
+        file.write_all(compacted.as_bytes())
+            .and_then(|_| file.sync_all())
+            .map_err(|error| error.to_string())?;
+        fs::rename(&temporary, path).map_err(|error| error.to_string())?;
+        let log = OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map_err(|error| error.to_string())?;
+        Ok(DiskStore { store, log })
+    }
+
+    fn append(&mut self, operation: char, tuple: &Tuple) {
+        let line = format!("{}{}\n", operation, format_tuple(tuple));
+        if let Err(error) = self.log.write_all(line.as_bytes()) {
+            eprintln!("Cannot write to the tuple space log: {}", error);
+        }
+    }
+}
+
+impl TupleStore for DiskStore {
+    fn out(&mut self, tuple: Tuple) -> Result<Option<Tuple>, String> {
+        self.store.out(tuple.clone())?;
+        self.append(LOG_OUT, &tuple);
+        Ok(None)
+    }
+
+    fn inp(&mut self, template: &Tuple) -> Option<Tuple> {
+        let tuple = self.store.inp(template)?;
+        self.append(LOG_IN, &tuple);
+        Some(tuple)
+    }
+
+    fn rdp(&mut self, template: &Tuple) -> Option<Tuple> {
+        self.store.rdp(template)
+    }
+
+    fn rdp_all(&self, template: &Tuple) -> Vec<Tuple> {
+        self.store.rdp_all(template)
+    }
+
+    fn len(&self) -> usize {
+        self.store.len()
+    }
+
+    fn tuples(&self) -> Vec<Tuple> {
+        self.store.tuples()
+    }
+}
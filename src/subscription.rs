@@ -0,0 +1,173 @@
+//! Module Subscription
+//!
+//! Subscriptions of clients to the tuples written into a tuple space. Every tuple written into
+//! the tuple space and matching the template of a subscription is pushed to the client as an
+//! unsolicited message `notify {id} {tuple}`, encoded like the responses of its session.
+
+use std::collections::HashMap;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use mio::{Token, Waker};
+use rustupolis::tuple::Tuple;
+
+use crate::codec::{tuple_elements, Encoding};
+use crate::constant::{NOTIFY, SPACE};
+use crate::provenance::Provenance;
+use crate::session::Session;
+
+/// Sends the messages pushed to a connection to the event loop of the TCP server, and wakes it up
+/// to write them.
+#[derive(Clone)]
+pub struct Notifier {
+    token: Token,
+    sender: Sender<(Token, Vec<u8>)>,
+    waker: Arc<Waker>,
+}
+
+impl Notifier {
+    pub fn new(token: Token, sender: Sender<(Token, Vec<u8>)>, waker: Arc<Waker>) -> Notifier {
+        Notifier {
+            token,
+            sender,
+            waker,
+        }
+    }
+
+    fn notify(&self, message: Vec<u8>) {
+        if self.sender.send((self.token, message)).is_ok() {
+            if let Err(error) = self.waker.wake() {
+                eprintln!("Cannot wake the server up: {}", error);
+            }
+        }
+    }
+}
+
+struct Subscription {
+    tuple_space_name: String,
+    template: Tuple,
+    encoding: Encoding,
+    notifier: Notifier,
+    attributes: Vec<String>,
+    // The attributes the session claimed and held when subscribing, and the expiration of its
+    // credential, to check its permission again before each notification.
+    claimed: Vec<String>,
+    held: Vec<String>,
+    expiration: Option<SystemTime>,
+}
+
+/// The subscriptions of every session, identified by the session and the id chosen by the client.
+#[derive(Clone, Default)]
+pub struct Subscriptions {
+    subscriptions: Arc<Mutex<HashMap<(u64, u64), Subscription>>>,
+}
+
+impl Subscriptions {
+    /// Subscribes the session to the tuples of the tuple space it is attached to which the
+    /// attributes may see, replacing the subscription with the same id. Returns `false` if the
+    /// session cannot receive notifications.
+    pub fn subscribe(
+        &self,
+        session: &Session,
+        id: u64,
+        template: Tuple,
+        attributes: Vec<String>,
+    ) -> bool {
+        match (session.notifier(), session.tuple_space()) {
+            (Some(notifier), Some(tuple_space)) => {
+                self.subscriptions.lock().unwrap().insert(
+                    (session.id(), id),
+                    Subscription {
+                        tuple_space_name: String::from(tuple_space.tuple_space_name()),
+                        template,
+                        encoding: session.encoding(),
+                        notifier: notifier.clone(),
+                        attributes,
+                        claimed: tuple_space.attributes().to_vec(),
+                        held: session.attributes().to_vec(),
+                        expiration: session.expiration(),
+                    },
+                );
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns `false` if the session has no subscription with this id.
+    pub fn unsubscribe(&self, session: &Session, id: u64) -> bool {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .remove(&(session.id(), id))
+            .is_some()
+    }
+
+    /// Removes the subscriptions of a closed session.
+    pub fn close_session(&self, session: &Session) {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .retain(|(session_id, _), _| *session_id != session.id());
+    }
+
+    /// Removes the subscriptions to a deleted tuple space.
+    pub fn remove_tuple_space(&self, tuple_space_name: &str) {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .retain(|_, subscription| subscription.tuple_space_name != tuple_space_name);
+    }
+
+    /// Notifies the subscribers of the tuple space matching the tuple and allowed to see it,
+    /// `may_read` checking again that the attributes claimed and held by their session may read
+    /// the tuple space. The subscriptions of the sessions whose credential expired are removed.
+    /// The messages are sent once the subscriptions are unlocked.
+    pub fn notify<F>(
+        &self,
+        tuple_space_name: &str,
+        tuple: &Tuple,
+        provenance: Option<&Provenance>,
+        may_read: F,
+    ) where
+        F: Fn(&[String], &[String]) -> bool,
+    {
+        let arity = tuple_elements(tuple).len();
+        let now = SystemTime::now();
+        let mut candidates = Vec::new();
+        {
+            let mut subscriptions = self.subscriptions.lock().unwrap();
+            subscriptions.retain(|_, subscription| {
+                subscription
+                    .expiration
+                    .is_none_or(|expiration| expiration > now)
+            });
+            for ((_, id), subscription) in subscriptions.iter() {
+                if subscription.tuple_space_name == tuple_space_name
+                    && tuple_elements(&subscription.template).len() == arity
+                    && subscription.template.matches(tuple)
+                    && provenance
+                        .is_none_or(|provenance| provenance.allows(&subscription.attributes))
+                {
+                    candidates.push((
+                        *id,
+                        subscription.encoding,
+                        subscription.notifier.clone(),
+                        subscription.claimed.clone(),
+                        subscription.held.clone(),
+                    ));
+                }
+            }
+        }
+        // The permissions are checked without the subscriptions locked, as checking them locks
+        // the permission tuple space.
+        for (id, encoding, notifier, claimed, held) in candidates {
+            if may_read(&claimed, &held) {
+                let mut message = format!("{} {}{}", NOTIFY, id, SPACE).into_bytes();
+                message.append(&mut encoding.encode_response(std::slice::from_ref(tuple)));
+                notifier.notify(message);
+            }
+        }
+    }
+}
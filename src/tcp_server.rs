@@ -1,19 +1,35 @@
 use aes_gcm::aead::{Aead, NewAead};
 use aes_gcm::{Aes128Gcm, Key, Nonce}; // Or `Aes128Gcm`
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io;
 use std::io::{Read, Write};
+use std::sync::mpsc;
+use std::sync::Arc;
 
 use mio::event::Event;
 use mio::net::{TcpListener, TcpStream};
-use mio::{Events, Interest, Poll, Registry, Token};
+use mio::{Events, Interest, Poll, Registry, Token, Waker};
 
-use crate::constant::{CONNECTED, OK, TUPLE_SPACE_ATTACHED, TUPLE_SPACE_ATTACHED_UPDATED};
+use crate::constant::{
+    AUTHENTICATED, CONNECTED, ENCODING_UPDATED, OK, TUPLE_SPACE_ATTACHED,
+    TUPLE_SPACE_ATTACHED_UPDATED,
+};
 use crate::repository::{Repository, RequestResponse};
-use crate::tuple_space::TupleSpace;
+use crate::session::Session;
+use crate::subscription::Notifier;
 
 // Setup some tokens to allow us to identify which event is for which socket.
 const SERVER: Token = Token(0);
+// Wakes the event loop up when notifications are waiting to be written.
+const WAKER: Token = Token(usize::MAX);
+
+/// A client connection, with the bytes waiting for the socket to be writable.
+struct Connection {
+    stream: TcpStream,
+    output: Vec<u8>,
+    // Whether the connection is registered for writable events.
+    writable: bool,
+}
 
 #[cfg(not(target_os = "wasi"))]
 pub fn launch_server<'a>(
@@ -38,15 +54,17 @@ pub fn launch_server<'a>(
     poll.registry()
         .register(&mut server, SERVER, Interest::READABLE)?;
 
-    let mut clients: HashMap<Token, TupleSpace> = HashMap::new();
+    let waker = Arc::new(Waker::new(poll.registry(), WAKER)?);
+    let (notification_sender, notifications) = mpsc::channel::<(Token, Vec<u8>)>();
+
+    let mut sessions: HashMap<Token, Session> = HashMap::new();
 
     // Map of `Token` -> `TcpStream`.
     let mut connections = HashMap::new();
     // Unique token for each incoming connection.
     let mut unique_token = Token(SERVER.0 + 1);
 
-    println!("You can connect to the TCP server using `ncat`:");
-    println!("ncat {} {}", ip_address, port);
+    println!("TCP server listening on {}:{}", ip_address, port);
 
     loop {
         poll.poll(&mut events, None)?;
@@ -78,8 +96,50 @@ pub fn launch_server<'a>(
                         Interest::READABLE.add(Interest::WRITABLE),
                     )?;
 
-                    connections.insert(token, connection);
+                    // The client is greeted once the connection is writable.
+                    connections.insert(
+                        token,
+                        Connection {
+                            stream: connection,
+                            output: CONNECTED.as_bytes().to_vec(),
+                            writable: true,
+                        },
+                    );
+                    sessions.insert(
+                        token,
+                        Session::with_notifier(
+                            Notifier::new(token, notification_sender.clone(), waker.clone()),
+                            address,
+                        ),
+                    );
                 },
+                WAKER => {
+                    let mut notified = HashSet::new();
+                    for (token, message) in notifications.try_iter() {
+                        if let Some(connection) = connections.get_mut(&token) {
+                            write_message(connection, key, &message);
+                            notified.insert(token);
+                        }
+                    }
+                    let mut closed = Vec::new();
+                    for token in notified {
+                        if let Some(connection) = connections.get_mut(&token) {
+                            if let Err(e) = flush(poll.registry(), token, connection) {
+                                println!("{}", e);
+                                closed.push(token);
+                            }
+                        }
+                    }
+                    for token in closed {
+                        close_connection(
+                            poll.registry(),
+                            token,
+                            &mut connections,
+                            &mut sessions,
+                            repository,
+                        )?;
+                    }
+                }
                 token => {
                     // Maybe received an event for a TCP connection.
                     let done = if let Some(connection) = connections.get_mut(&token) {
@@ -87,7 +147,7 @@ pub fn launch_server<'a>(
                             poll.registry(),
                             connection,
                             event,
-                            &mut clients,
+                            &mut sessions,
                             repository,
                             key,
                         ) {
@@ -99,9 +159,13 @@ pub fn launch_server<'a>(
                         false
                     };
                     if done {
-                        if let Some(mut connection) = connections.remove(&token) {
-                            poll.registry().deregister(&mut connection)?;
-                        }
+                        close_connection(
+                            poll.registry(),
+                            token,
+                            &mut connections,
+                            &mut sessions,
+                            repository,
+                        )?;
                     }
                 }
             }
@@ -109,6 +173,22 @@ pub fn launch_server<'a>(
     }
 }
 
+fn close_connection(
+    registry: &Registry,
+    token: Token,
+    connections: &mut HashMap<Token, Connection>,
+    sessions: &mut HashMap<Token, Session>,
+    repository: &Repository,
+) -> io::Result<()> {
+    if let Some(mut connection) = connections.remove(&token) {
+        registry.deregister(&mut connection.stream)?;
+    }
+    if let Some(session) = sessions.remove(&token) {
+        repository.close_session(&session);
+    }
+    Ok(())
+}
+
 fn next(current: &mut Token) -> Token {
     let next = current.0;
     current.0 += 1;
@@ -118,24 +198,14 @@ fn next(current: &mut Token) -> Token {
 /// Returns `true` if the connection is done.
 fn handle_connection_event<'a>(
     registry: &Registry,
-    connection: &mut TcpStream,
+    connection: &mut Connection,
     event: &Event,
-    clients: &mut HashMap<Token, TupleSpace>,
+    sessions: &mut HashMap<Token, Session>,
     repository: &'a Repository,
     key: &str,
 ) -> io::Result<bool> {
     if event.is_writable() {
-        match connection.write(CONNECTED.as_ref()) {
-            Ok(n) if n < CONNECTED.len() => return Err(io::ErrorKind::WriteZero.into()),
-            Ok(_) => registry.reregister(connection, event.token(), Interest::READABLE)?,
-            Err(ref err) if would_block(err) => {}
-            Err(ref err) if interrupted(err) => {
-                return handle_connection_event(
-                    registry, connection, event, clients, repository, key,
-                );
-            }
-            Err(err) => return Err(err),
-        }
+        flush(registry, event.token(), connection)?;
     }
 
     if event.is_readable() {
@@ -144,7 +214,7 @@ fn handle_connection_event<'a>(
         let mut bytes_read = 0;
         // We can (maybe) read from the connection.
         loop {
-            match connection.read(&mut received_data[bytes_read..]) {
+            match connection.stream.read(&mut received_data[bytes_read..]) {
                 Ok(0) => {
                     connection_closed = true;
                     break;
@@ -167,48 +237,41 @@ fn handle_connection_event<'a>(
         if bytes_read != 0 {
             let received_data = &received_data[..bytes_read];
             //if let Ok(str_buf) = from_utf8(received_data) {
-            let client_option = clients.get(&event.token());
-            //println!("{}", received_data.to_ascii_lowercase());
-            let result = repository.manage_request(received_data, client_option, key);
+            let session = sessions.entry(event.token()).or_default();
+            let result = repository.manage_request(received_data, session, key);
 
             match result {
                 RequestResponse::SpaceResponse(client) => {
-                    match clients.insert(event.token(), client) {
-                        None => {
-                            if let Err(e) =
-                                connection.write(&*encrypt_data(key, TUPLE_SPACE_ATTACHED.as_ref()))
-                            {
-                                println!("{}", e)
-                            }
-                        }
-                        Some(_) => {
-                            if let Err(e) = connection
-                                .write(&*encrypt_data(key, TUPLE_SPACE_ATTACHED_UPDATED.as_ref()))
-                            {
-                                println!("{}", e)
-                            }
-                        }
+                    let message = if session.attach(client) {
+                        TUPLE_SPACE_ATTACHED_UPDATED
+                    } else {
+                        TUPLE_SPACE_ATTACHED
                     };
+                    write_message(connection, key, message.as_bytes());
+                }
+                RequestResponse::EncodingResponse(encoding) => {
+                    session.set_encoding(encoding);
+                    write_message(connection, key, ENCODING_UPDATED.as_bytes());
+                }
+                RequestResponse::AuthResponse(attributes, expiration) => {
+                    session.authenticate(attributes, expiration);
+                    write_message(connection, key, AUTHENTICATED.as_bytes());
                 }
                 RequestResponse::NoResponse(x) => {
-                    if let Err(e) = connection.write(&*encrypt_data(key, x.as_ref())) {
-                        println!("{}", e)
-                    }
+                    write_message(connection, key, x.as_ref());
                 }
                 RequestResponse::OkResponse() => {
-                    if let Err(e) = connection.write(&*encrypt_data(key, OK.as_ref())) {
-                        println!("{}", e)
-                    }
+                    write_message(connection, key, OK.as_ref());
                 }
                 RequestResponse::DataResponse(tuple_list) => {
-                    if let Err(e) = connection.write(&*encrypt_data(key, tuple_list.as_ref())) {
-                        println!("{}", e)
-                    }
+                    let data = session.encoding().encode_response(&tuple_list);
+                    write_message(connection, key, &data);
                 }
             }
             // } else {
             //     println!("Received (none UTF-8) data: {:?}", received_data);
             // }
+            flush(registry, event.token(), connection)?;
         }
 
         if connection_closed {
@@ -227,14 +290,49 @@ fn interrupted(err: &io::Error) -> bool {
     err.kind() == io::ErrorKind::Interrupted
 }
 
-fn encrypt_data(key: &str, text: &str) -> Vec<u8> {
-    println!("{}",text);
+/// Queues an encrypted message, prefixed by its length as a big-endian `u32` so that the client
+/// can tell responses and notifications apart. The message is written by `flush`.
+fn write_message(connection: &mut Connection, key: &str, text: &[u8]) {
+    let data = encrypt_data(key, text);
+    connection
+        .output
+        .extend_from_slice(&(data.len() as u32).to_be_bytes());
+    connection.output.extend_from_slice(&data);
+}
+
+/// Writes as much of the queued bytes as the socket takes, and only asks to be told when it is
+/// writable while bytes are left.
+fn flush(registry: &Registry, token: Token, connection: &mut Connection) -> io::Result<()> {
+    let mut written = 0;
+    while written < connection.output.len() {
+        match connection.stream.write(&connection.output[written..]) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(n) => written += n,
+            Err(ref err) if would_block(err) => break,
+            Err(ref err) if interrupted(err) => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    connection.output.drain(..written);
+    let writable = !connection.output.is_empty();
+    if writable == connection.writable {
+        return Ok(());
+    }
+    connection.writable = writable;
+    let interest = if writable {
+        Interest::READABLE.add(Interest::WRITABLE)
+    } else {
+        Interest::READABLE
+    };
+    registry.reregister(&mut connection.stream, token, interest)
+}
+
+fn encrypt_data(key: &str, text: &[u8]) -> Vec<u8> {
+    println!("{}", String::from_utf8_lossy(text));
     let key = Key::from_slice(key.as_ref());
     let cipher = Aes128Gcm::new(key);
 
     let nonce = Nonce::from_slice(b"unique nonce");
 
-    return cipher
-        .encrypt(nonce, text.as_ref())
-        .expect("encryption failure!");
+    return cipher.encrypt(nonce, text).expect("encryption failure!");
 }
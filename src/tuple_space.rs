@@ -1,17 +1,16 @@
 use std::sync::{Arc, Mutex};
 
-use rustupolis::space::Space;
-use rustupolis::store::SimpleStore;
+use crate::managed_space::ManagedSpace;
 
 pub struct TupleSpace {
-    tuple_space: Arc<Mutex<Space<SimpleStore>>>,
+    tuple_space: Arc<Mutex<ManagedSpace>>,
     tuple_space_name: String,
     attributes: Vec<String>,
 }
 
 impl TupleSpace {
     pub fn new(
-        tuple_space: Arc<Mutex<Space<SimpleStore>>>,
+        tuple_space: Arc<Mutex<ManagedSpace>>,
         attributes: Vec<String>,
         tuple_space_name: &str,
     ) -> TupleSpace {
@@ -23,7 +22,7 @@ impl TupleSpace {
         }
     }
 
-    pub fn tuple_space(&self) -> &Arc<Mutex<Space<SimpleStore>>> {
+    pub fn tuple_space(&self) -> &Arc<Mutex<ManagedSpace>> {
         &self.tuple_space
     }
 
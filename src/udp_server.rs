@@ -3,14 +3,18 @@ use aes_gcm::{Aes128Gcm, Key, Nonce}; // Or `Aes128Gcm`
 use std::collections::HashMap;
 use std::io;
 use std::net::SocketAddr;
+use std::time::{Duration, Instant};
 
 use log::{warn};
 use mio::net::UdpSocket;
 use mio::{Events, Interest, Poll, Token};
 
-use crate::constant::{OK, TUPLE_SPACE_ATTACHED, TUPLE_SPACE_ATTACHED_UPDATED};
+use crate::constant::{
+    AUTHENTICATED, ENCODING_UPDATED, MAX_UDP_SESSIONS, OK, TUPLE_SPACE_ATTACHED,
+    TUPLE_SPACE_ATTACHED_UPDATED, UDP_SESSION_IDLE_TIMEOUT,
+};
 use crate::repository::{Repository, RequestResponse};
-use crate::tuple_space::TupleSpace;
+use crate::session::Session;
 
 // A token to allow us to identify which event is for the `UdpSocket`.
 const UDP_SOCKET: Token = Token(0);
@@ -31,7 +35,8 @@ pub(crate) fn launch_server(
 
     let mut socket = UdpSocket::bind(addr)?;
 
-    let mut client_list: HashMap<SocketAddr, TupleSpace> = HashMap::new();
+    // The sessions of the peers, with the time they were last heard from.
+    let mut sessions: HashMap<SocketAddr, (Session, Instant)> = HashMap::new();
     // Register our socket with the token defined above and an interest in being
     // `READABLE`.
     poll.registry()
@@ -54,32 +59,47 @@ pub(crate) fn launch_server(
                 UDP_SOCKET => loop {
                     match socket.recv_from(&mut buf) {
                         Ok((packet_size, source_address)) => {
-                            let client = client_list.get(&source_address);
+                            if !sessions.contains_key(&source_address) {
+                                evict_sessions(&mut sessions, repository);
+                            }
+                            let (session, last_seen) =
+                                sessions.entry(source_address).or_insert_with(|| {
+                                    (Session::with_peer(source_address), Instant::now())
+                                });
+                            *last_seen = Instant::now();
                             let result =
-                                repository.manage_request(&buf[..packet_size], client, key);
+                                repository.manage_request(&buf[..packet_size], session, key);
                             match result {
                                 RequestResponse::SpaceResponse(new_client) => {
-                                    match client_list.insert(source_address, new_client) {
-                                        None => {
-                                            if let Err(e) = socket.send_to(
-                                                &*encrypt_data(key, TUPLE_SPACE_ATTACHED.as_ref()),
-                                                source_address,
-                                            ) {
-                                                println!("{}", e)
-                                            }
-                                        }
-                                        Some(_) => {
-                                            if let Err(e) = socket.send_to(
-                                                &*encrypt_data(
-                                                    key,
-                                                    TUPLE_SPACE_ATTACHED_UPDATED.as_ref(),
-                                                ),
-                                                source_address,
-                                            ) {
-                                                println!("{}", e)
-                                            }
-                                        }
+                                    let message = if session.attach(new_client) {
+                                        TUPLE_SPACE_ATTACHED_UPDATED
+                                    } else {
+                                        TUPLE_SPACE_ATTACHED
                                     };
+                                    if let Err(e) = socket.send_to(
+                                        &encrypt_data(key, message.as_bytes()),
+                                        source_address,
+                                    ) {
+                                        println!("{}", e)
+                                    }
+                                }
+                                RequestResponse::EncodingResponse(encoding) => {
+                                    session.set_encoding(encoding);
+                                    if let Err(e) = socket.send_to(
+                                        &encrypt_data(key, ENCODING_UPDATED.as_bytes()),
+                                        source_address,
+                                    ) {
+                                        println!("{}", e)
+                                    }
+                                }
+                                RequestResponse::AuthResponse(attributes, expiration) => {
+                                    session.authenticate(attributes, expiration);
+                                    if let Err(e) = socket.send_to(
+                                        &encrypt_data(key, AUTHENTICATED.as_bytes()),
+                                        source_address,
+                                    ) {
+                                        println!("{}", e)
+                                    }
                                 }
                                 RequestResponse::NoResponse(x) => {
                                     if let Err(e) = socket
@@ -96,8 +116,9 @@ pub(crate) fn launch_server(
                                     }
                                 }
                                 RequestResponse::DataResponse(tuple_list) => {
+                                    let data = session.encoding().encode_response(&tuple_list);
                                     if let Err(e) = socket.send_to(
-                                        &*encrypt_data(key, tuple_list.as_ref()),
+                                        &encrypt_data(key, &data),
                                         source_address,
                                     ) {
                                         println!("{}", e)
@@ -128,15 +149,41 @@ pub(crate) fn launch_server(
         }
     }
 
-    fn encrypt_data(key: &str, text: &str) -> Vec<u8> {
-        println!("{}",text);
+    /// Makes room for a new session: closes the sessions idle for longer than
+    /// `UDP_SESSION_IDLE_TIMEOUT` seconds, then the least recently heard from one if there are
+    /// still `MAX_UDP_SESSIONS` of them.
+    fn evict_sessions(
+        sessions: &mut HashMap<SocketAddr, (Session, Instant)>,
+        repository: &Repository,
+    ) {
+        let idle_timeout = Duration::from_secs(UDP_SESSION_IDLE_TIMEOUT);
+        sessions.retain(|_, (session, last_seen)| {
+            let idle = last_seen.elapsed() >= idle_timeout;
+            if idle {
+                repository.close_session(session);
+            }
+            !idle
+        });
+        if sessions.len() >= MAX_UDP_SESSIONS {
+            let oldest = sessions
+                .iter()
+                .min_by_key(|(_, (_, last_seen))| *last_seen)
+                .map(|(address, _)| *address);
+            if let Some((session, _)) = oldest.and_then(|address| sessions.remove(&address)) {
+                repository.close_session(&session);
+            }
+        }
+    }
+
+    fn encrypt_data(key: &str, text: &[u8]) -> Vec<u8> {
+        println!("{}", String::from_utf8_lossy(text));
         let key = Key::from_slice(key.as_ref());
         let cipher = Aes128Gcm::new(key);
 
         let nonce = Nonce::from_slice(b"unique nonce");
 
         return cipher
-            .encrypt(nonce, text.as_ref())
+            .encrypt(nonce, text)
             .expect("encryption failure!");
     }
 }
@@ -0,0 +1,69 @@
+//! Helpers shared by the tests driving a repository like a server does.
+#![allow(dead_code)]
+
+use aes_gcm::aead::{Aead, NewAead};
+use aes_gcm::{Aes128Gcm, Key, Nonce};
+use rustupolis_server::lexing::format_tuples;
+use rustupolis_server::managed_space::SpaceLimits;
+use rustupolis_server::repository::{Repository, RequestResponse};
+use rustupolis_server::session::Session;
+use rustupolis_server::store::StoreKind;
+
+pub const KEY: &str = "an example key !";
+
+/// Sends a request to the repository and applies its response to the session, like the servers
+/// do. Returns the text of the response, the tuples being formatted with the text grammar.
+pub fn send(repository: &Repository, session: &mut Session, request: &str) -> String {
+    let key = Key::from_slice(KEY.as_bytes());
+    let cipher = Aes128Gcm::new(key);
+    let nonce = Nonce::from_slice(b"unique nonce");
+    let request = cipher.encrypt(nonce, request.as_bytes()).unwrap();
+    match repository.manage_request(&request, session, KEY) {
+        RequestResponse::SpaceResponse(tuple_space) => {
+            session.attach(tuple_space);
+            String::from("Tuple space attached")
+        }
+        RequestResponse::EncodingResponse(encoding) => {
+            session.set_encoding(encoding);
+            String::from("Encoding updated")
+        }
+        RequestResponse::AuthResponse(attributes, expiration) => {
+            session.authenticate(attributes, expiration);
+            String::from("Authenticated")
+        }
+        RequestResponse::DataResponse(tuples) => format_tuples(&tuples),
+        RequestResponse::OkResponse() => String::from("Successful request"),
+        RequestResponse::NoResponse(message) => message,
+    }
+}
+
+pub fn attributes(attributes: &[&str]) -> Vec<String> {
+    attributes
+        .iter()
+        .map(|attribute| String::from(*attribute))
+        .collect()
+}
+
+/// Returns a session authenticated with the attributes.
+pub fn session(attributes: &[&str]) -> Session {
+    let mut session = Session::new();
+    session.authenticate(self::attributes(attributes), None);
+    session
+}
+
+/// Returns a repository with unlimited tuple spaces in memory, each one created with its
+/// attributes.
+pub fn repository_with(spaces: &[(&str, &[&str])]) -> Repository {
+    let repository = Repository::new("admin");
+    for (name, attributes) in spaces {
+        repository
+            .add_tuple_space(
+                String::from(*name),
+                self::attributes(attributes),
+                StoreKind::default(),
+                SpaceLimits::unlimited(),
+            )
+            .unwrap();
+    }
+    repository
+}
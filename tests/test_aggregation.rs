@@ -0,0 +1,53 @@
+use rustupolis::tuple;
+use rustupolis::tuple::E;
+use rustupolis_server::aggregation::Aggregation;
+
+fn readings() -> Vec<rustupolis::tuple::Tuple> {
+    vec![
+        tuple![E::str("temp"), E::str("kitchen"), E::I(21)],
+        tuple![E::str("temp"), E::str("kitchen"), E::I(23)],
+        tuple![E::str("temp"), E::str("garage"), E::D(12.5)],
+        tuple![E::str("temp"), E::str("garage"), E::str("offline")],
+    ]
+}
+
+#[test]
+fn aggregate_all() {
+    let tuples = readings();
+    assert_eq!(
+        Aggregation::Count.aggregate(&tuples, None),
+        Some(tuple![E::I(4)])
+    );
+    assert_eq!(
+        Aggregation::Sum(2).aggregate(&tuples, None),
+        Some(tuple![E::D(56.5)])
+    );
+    assert_eq!(
+        Aggregation::Min(2).aggregate(&tuples, None),
+        Some(tuple![E::D(12.5)])
+    );
+    assert_eq!(
+        Aggregation::Max(2).aggregate(&tuples, None),
+        Some(tuple![E::I(23)])
+    );
+    assert_eq!(Aggregation::Mean(1).aggregate(&tuples, None), None);
+    assert_eq!(
+        Aggregation::Count.aggregate(&[], None),
+        Some(tuple![E::I(0)])
+    );
+}
+
+#[test]
+fn aggregate_by_group() {
+    assert_eq!(
+        Aggregation::Mean(2).aggregate(&readings(), Some(1)),
+        Some(tuple![
+            E::T(tuple![E::str("garage"), E::D(12.5)]),
+            E::T(tuple![E::str("kitchen"), E::D(22.0)])
+        ])
+    );
+    assert_eq!(
+        Aggregation::Sum(2).aggregate(&readings()[..2], Some(1)),
+        Some(tuple![E::T(tuple![E::str("kitchen"), E::I(44)])])
+    );
+}
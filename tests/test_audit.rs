@@ -0,0 +1,145 @@
+mod common;
+
+use std::fs;
+use std::io::Write;
+
+use common::{send, session};
+
+use rustupolis_server::audit::{AuditLog, AuditRecord, ALLOW, DENY};
+use rustupolis_server::repository::Repository;
+
+fn record(command: &str, tuple_space: &str, result: &str) -> AuditRecord {
+    AuditRecord::new(
+        Some(String::from("127.0.0.1:4000")),
+        Some(String::from("tcp")),
+        vec![String::from("admin")],
+        String::from(command),
+        Some(String::from(tuple_space)),
+        result,
+        None,
+    )
+}
+
+#[test]
+fn test_record_and_query() {
+    let directory = std::env::temp_dir().join(format!("rustupolis_audit_{}", std::process::id()));
+    let path = directory.join("audit.log");
+    let audit_log = AuditLog::open(&path, 1 << 20, 2).unwrap();
+    audit_log.record(&record("out", "DATA", ALLOW)).unwrap();
+    audit_log.record(&record("delete", "DATA", DENY)).unwrap();
+    audit_log.record(&record("read", "OTHER", ALLOW)).unwrap();
+
+    let (records, malformed) = audit_log.query(|_| true, None).unwrap();
+    assert_eq!(records.len(), 3);
+    assert_eq!(malformed, 0);
+    assert_eq!(records[1].command, "delete");
+    assert_eq!(records[1].tuple_space.as_deref(), Some("DATA"));
+    assert_eq!(records[1].attributes, vec![String::from("admin")]);
+    assert_eq!(records[1].message, None);
+    let (denied, _) = audit_log
+        .query(|record| record.result == DENY, None)
+        .unwrap();
+    assert_eq!(denied.len(), 1);
+    assert_eq!(denied[0].command, "delete");
+    let (last, _) = audit_log.query(|_| true, Some(2)).unwrap();
+    assert_eq!(last.len(), 2);
+    assert_eq!(last[1].command, "read");
+
+    // Reopening the log appends to it.
+    drop(audit_log);
+    let audit_log = AuditLog::open(&path, 1 << 20, 2).unwrap();
+    audit_log.record(&record("in", "DATA", ALLOW)).unwrap();
+    assert_eq!(audit_log.query(|_| true, None).unwrap().0.len(), 4);
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn test_rotation() {
+    let directory =
+        std::env::temp_dir().join(format!("rustupolis_audit_rotation_{}", std::process::id()));
+    let path = directory.join("audit.log");
+    let line = record("out", "DATA", ALLOW).to_json().to_string().len() as u64 + 1;
+    // Two records per file, the current one and two previous ones.
+    let audit_log = AuditLog::open(&path, 2 * line + 10, 2).unwrap();
+    for _ in 0..7 {
+        audit_log.record(&record("out", "DATA", ALLOW)).unwrap();
+    }
+    audit_log.record(&record("in", "DATA", ALLOW)).unwrap();
+    assert!(directory.join("audit.log.1").exists());
+    assert!(directory.join("audit.log.2").exists());
+    assert!(!directory.join("audit.log.3").exists());
+
+    // The oldest records were removed with the oldest file.
+    let (records, _) = audit_log.query(|_| true, None).unwrap();
+    assert_eq!(records.len(), 6);
+    assert_eq!(records.last().unwrap().command, "in");
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn test_skip_malformed_lines() {
+    let directory =
+        std::env::temp_dir().join(format!("rustupolis_audit_malformed_{}", std::process::id()));
+    let path = directory.join("audit.log");
+    let audit_log = AuditLog::open(&path, 1 << 20, 1).unwrap();
+    audit_log.record(&record("out", "DATA", ALLOW)).unwrap();
+    let mut log = fs::OpenOptions::new().append(true).open(&path).unwrap();
+    writeln!(log, "{{\"command\":").unwrap();
+    writeln!(log, "{{\"command\":\"out\"}}").unwrap();
+    audit_log.record(&record("in", "DATA", ALLOW)).unwrap();
+
+    let (records, malformed) = audit_log.query(|_| true, None).unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[1].command, "in");
+    assert_eq!(malformed, 2);
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn test_repository_audit_log() {
+    let repository = Repository::new("admin");
+    assert!(repository.audit_records(|_| true, None).is_err());
+
+    let directory = std::env::temp_dir().join(format!(
+        "rustupolis_audit_repository_{}",
+        std::process::id()
+    ));
+    repository
+        .enable_audit_log(&directory.join("audit.log"), 1 << 20, 1)
+        .unwrap();
+    assert_eq!(
+        repository.audit_records(|_| true, None).unwrap(),
+        (vec![], 0)
+    );
+
+    let mut admin = session(&["admin"]);
+    let mut guest = session(&["guest"]);
+    send(&repository, &mut admin, "create admin DATA admin");
+    send(&repository, &mut admin, "attach DATA");
+    send(&repository, &mut admin, "out (\"temp\",21)");
+    send(&repository, &mut guest, "attach DATA");
+    let (records, malformed) = repository.audit_records(|_| true, None).unwrap();
+    assert_eq!(malformed, 0);
+    let commands: Vec<(&str, Option<&str>, &str)> = records
+        .iter()
+        .map(|record| {
+            (
+                record.command.as_str(),
+                record.tuple_space.as_deref(),
+                record.result.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        commands,
+        vec![
+            ("create", Some("DATA"), ALLOW),
+            ("attach", Some("DATA"), ALLOW),
+            ("out", Some("DATA"), ALLOW),
+            ("attach", Some("DATA"), DENY),
+        ]
+    );
+    assert_eq!(records[3].attributes, vec![String::from("guest")]);
+    assert_eq!(records[3].message.as_deref(), Some("ERROR - No permission"));
+    fs::remove_dir_all(&directory).unwrap();
+}
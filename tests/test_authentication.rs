@@ -0,0 +1,74 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rustupolis_server::credential::{Credential, SigningKey};
+use rustupolis_server::repository::Repository;
+
+#[test]
+fn test_credential() {
+    let repository = Repository::new("admin");
+    assert_eq!(repository.authenticate("token"), None);
+
+    repository.add_credential("token", vec![String::from("admin"), String::from("staff")]);
+    assert_eq!(
+        repository.authenticate("token"),
+        Some((vec![String::from("admin"), String::from("staff")], None))
+    );
+    assert_eq!(repository.authenticate("other"), None);
+
+    repository.add_credential("token", vec![String::from("staff")]);
+    assert_eq!(
+        repository.authenticate("token"),
+        Some((vec![String::from("staff")], None))
+    );
+
+    assert!(repository.remove_credential("token"));
+    assert!(!repository.remove_credential("token"));
+    assert_eq!(repository.authenticate("token"), None);
+}
+
+#[test]
+fn test_signed_credential() {
+    let repository = Repository::new("admin");
+    let issuer = SigningKey::from_bytes(&[7; 32]);
+    let other = SigningKey::from_bytes(&[8; 32]);
+    let expiration = SystemTime::now() + Duration::from_secs(60);
+    let attributes = vec![String::from("siteA"), String::from("operator")];
+    let credential = Credential::issue(&issuer, attributes.clone(), expiration).unwrap();
+
+    let token = credential.to_token();
+    assert!(!token.contains(char::is_whitespace));
+    let read = Credential::from_token(&token).unwrap();
+    assert_eq!(read, credential);
+    assert!(read.is_signed_by(&issuer.verifying_key()));
+    assert!(!read.is_signed_by(&other.verifying_key()));
+
+    // Not trusted until the issuer is added.
+    assert_eq!(repository.authenticate(&token), None);
+    repository.add_issuer(issuer.verifying_key());
+    let (authenticated, expires) = repository.authenticate(&token).unwrap();
+    assert_eq!(authenticated, attributes);
+    assert_eq!(expires, Some(credential.expiration()));
+
+    let forged = Credential::issue(&other, vec![String::from("admin")], expiration).unwrap();
+    assert_eq!(repository.authenticate(&forged.to_token()), None);
+
+    // A token whose attributes were changed no longer matches its signature.
+    let (_, signature) = token.split_once('.').unwrap();
+    let seconds = credential
+        .expiration()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let content: String = format!("{}\nadmin", seconds)
+        .bytes()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    let tampered = format!("{}.{}", content, signature);
+    assert!(Credential::from_token(&tampered).is_ok());
+    assert_eq!(repository.authenticate(&tampered), None);
+
+    let expired = Credential::issue(&issuer, attributes, SystemTime::now()).unwrap();
+    assert!(expired.is_expired());
+    assert_eq!(repository.authenticate(&expired.to_token()), None);
+    assert!(Credential::from_token("not a token").is_err());
+}
@@ -0,0 +1,87 @@
+use rustupolis::tuple;
+use rustupolis::tuple::E;
+use rustupolis_server::codec::{decode_tuple, encode_tuple, encode_tuples, Encoding, TAG_TUPLE};
+
+#[test]
+fn test_binary_round_trip() {
+    let tuple = tuple![
+        E::str("temp"),
+        E::I(-21),
+        E::D(0.1 + 0.2),
+        E::T(tuple![E::str("nested, \"quoted\""), E::Any]),
+    ];
+    let mut buffer = Vec::new();
+    encode_tuple(&tuple, &mut buffer);
+    assert_eq!(decode_tuple(&buffer), Ok(tuple));
+}
+
+#[test]
+fn test_binary_request_payload() {
+    let tuples = vec![tuple![E::str("temp"), E::Any], tuple![E::I(1)]];
+    let payload = rustupolis_server::codec::encode_tuples(&tuples);
+    assert_eq!(Encoding::Binary.decode_tuples(&payload), Ok(tuples));
+    assert!(Encoding::Binary
+        .decode_tuples(&payload[..payload.len() - 1])
+        .is_err());
+}
+
+#[test]
+fn test_leading_tuples() {
+    let tuples = vec![tuple![E::str("state"), E::Any], tuple![E::I(1)]];
+    let text = b"(\"state\",_), (1) ; out (\"state\",\"done\")";
+    assert_eq!(
+        Encoding::Text.decode_leading_tuples(text),
+        Ok((tuples.clone(), 17))
+    );
+    let mut payload = encode_tuples(&tuples);
+    let length = payload.len();
+    payload.extend_from_slice(b" ; out");
+    assert_eq!(
+        Encoding::Binary.decode_leading_tuples(&payload),
+        Ok((tuples, length))
+    );
+}
+
+#[test]
+fn test_json_round_trip() {
+    let tuple = tuple![
+        E::str("temp"),
+        E::I(21),
+        E::D(20.5),
+        E::D(f64::INFINITY),
+        E::T(tuple![E::Any, E::None]),
+    ];
+    let json = rustupolis_server::json::tuple_to_json_string(&tuple);
+    assert_eq!(
+        json,
+        r#"[{"string":"temp"},{"int":21},{"float":20.5},{"float":"inf"},{"tuple":[{"any":null},{"none":null}]}]"#
+    );
+    assert_eq!(
+        rustupolis_server::json::tuple_from_json_str(&json),
+        Ok(tuple)
+    );
+    assert!(rustupolis_server::json::tuple_from_json_str(r#"[{"int":2.5}]"#).is_err());
+}
+
+/// The `MAX_NESTING_DEPTH` of the server.
+const MAX_NESTING_DEPTH: usize = 64;
+
+#[test]
+fn test_nesting_depth() {
+    let nested = |depth: usize| {
+        let mut tuple = tuple![];
+        for _ in 1..depth {
+            tuple = tuple![E::T(tuple)];
+        }
+        let mut buffer = Vec::new();
+        encode_tuple(&tuple, &mut buffer);
+        buffer
+    };
+    assert!(decode_tuple(&nested(MAX_NESTING_DEPTH)).is_ok());
+    assert!(decode_tuple(&nested(MAX_NESTING_DEPTH + 1)).is_err());
+
+    // A tuple holding a tuple, down to the end of a large payload.
+    let payload: Vec<u8> = [TAG_TUPLE, 0, 0, 0, 1].repeat(1 << 20);
+    assert!(decode_tuple(&payload).is_err());
+    assert!(Encoding::Binary.decode_tuples(&payload).is_err());
+}
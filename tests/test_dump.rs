@@ -0,0 +1,128 @@
+mod common;
+
+use common::{send, session};
+use rustupolis::tuple;
+use rustupolis::tuple::E;
+use rustupolis_server::dump::DumpFormat;
+use rustupolis_server::managed_space::{OverflowPolicy, SpaceLimits};
+use rustupolis_server::repository::Repository;
+use rustupolis_server::store::StoreKind;
+
+#[test]
+fn write_and_read_back() {
+    let tuples = vec![
+        tuple![
+            E::str("temp \"kitchen\""),
+            E::I(-21),
+            E::D(20.0),
+            E::T(tuple![E::D(f64::INFINITY), E::T(tuple![E::str("nested")])])
+        ],
+        tuple![E::None],
+    ];
+    for format in [DumpFormat::Text, DumpFormat::JsonLines] {
+        let path = std::env::temp_dir().join(format!(
+            "rustupolis_dump_{}_{}",
+            std::process::id(),
+            format.name()
+        ));
+        format.write(&path, &tuples).unwrap();
+        let read = format.read(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read, Ok(tuples.clone()));
+    }
+}
+
+#[test]
+fn report_invalid_lines() {
+    let path = std::env::temp_dir().join(format!("rustupolis_dump_{}_invalid", std::process::id()));
+    std::fs::write(&path, "(1)\n\n(2\n").unwrap();
+    let read = DumpFormat::Text.read(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert!(read.unwrap_err().starts_with("line 3"));
+}
+
+#[test]
+fn import_all_or_nothing() {
+    let repository = Repository::new("admin");
+    repository
+        .add_tuple_space(
+            String::from("archive"),
+            vec![String::from("admin")],
+            StoreKind::default(),
+            SpaceLimits::new(Some(3), None, OverflowPolicy::Reject),
+        )
+        .unwrap();
+    let mut session = session(&["admin"]);
+    send(&repository, &mut session, "attach archive");
+    send(&repository, &mut session, "out (\"temp\",1)");
+    let path = std::env::temp_dir().join(format!("rustupolis_dump_{}_import", std::process::id()));
+
+    // The third tuple does not fit, so none of the file is written.
+    std::fs::write(
+        &path,
+        "(\"temp\",2)\n(\"temp\",1)\n(\"temp\",3)\n(\"temp\",4)\n",
+    )
+    .unwrap();
+    let imported = repository.import_tuple_space("archive", &path, DumpFormat::Text);
+    assert_eq!(imported, Err("ERROR - The tuple space is full"));
+    assert_eq!(
+        send(&repository, &mut session, "read (\"temp\",_)"),
+        "(\"temp\",1)"
+    );
+
+    std::fs::write(&path, "(\"temp\",2)\n(\"temp\",1)\n").unwrap();
+    let imported = repository.import_tuple_space("archive", &path, DumpFormat::Text);
+    assert_eq!(imported, Ok(2));
+    assert_eq!(
+        send(&repository, &mut session, "describe archive admin"),
+        "(\"archive\",2,38,(\"read\",\"in\",\"out\",\"delete\"))"
+    );
+
+    let imported = repository.import_tuple_space("unknown", &path, DumpFormat::Text);
+    assert_eq!(imported, Err("ERROR - Tuple space not found"));
+    std::fs::write(&path, "(\"temp\",\n").unwrap();
+    let imported = repository.import_tuple_space("archive", &path, DumpFormat::Text);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(imported, Err("ERROR - The dump cannot be read or written"));
+}
+
+#[test]
+fn export_restricted_tuples() {
+    let repository = Repository::new("admin");
+    for name in ["sensors", "archive"] {
+        repository
+            .add_tuple_space(
+                String::from(name),
+                vec![String::from("admin")],
+                StoreKind::default(),
+                SpaceLimits::unlimited(),
+            )
+            .unwrap();
+    }
+    let mut session = session(&["admin"]);
+    send(&repository, &mut session, "attach sensors");
+    send(&repository, &mut session, "out acl=admin (\"secret\",1)");
+    send(&repository, &mut session, "out (\"temp\",21)");
+    let path =
+        std::env::temp_dir().join(format!("rustupolis_dump_{}_restricted", std::process::id()));
+
+    // The access control list would be lost, so nothing is written.
+    let exported = repository.export_tuple_space("sensors", &path, DumpFormat::Text);
+    assert_eq!(
+        exported,
+        Err("ERROR - Dumps do not keep access control lists")
+    );
+    assert!(!path.exists());
+
+    send(&repository, &mut session, "in (\"secret\",_)");
+    let exported = repository.export_tuple_space("sensors", &path, DumpFormat::Text);
+    assert_eq!(exported, Ok(1));
+    let imported = repository.import_tuple_space("archive", &path, DumpFormat::Text);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(imported, Ok(1));
+    send(&repository, &mut session, "attach archive");
+    assert_eq!(
+        send(&repository, &mut session, "read (_,_)"),
+        "(\"temp\",21)"
+    );
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{repository_with, send, session};
+use rustupolis_server::managed_space::{OverflowPolicy, SpaceLimits};
+use rustupolis_server::repository::Repository;
+use rustupolis_server::store::StoreKind;
+
+fn repository() -> Repository {
+    repository_with(&[("sensors", &["admin"])])
+}
+
+fn later(seconds: u64) -> Instant {
+    Instant::now() + Duration::from_secs(seconds)
+}
+
+#[test]
+fn test_ttl() {
+    let repository = repository();
+    let mut session = session(&["admin"]);
+    send(&repository, &mut session, "attach sensors");
+    send(&repository, &mut session, "out ttl=60 (\"temp\",21)");
+    send(&repository, &mut session, "out (\"wind\",3)");
+
+    repository.sweep_expirations(Instant::now());
+    assert_eq!(
+        send(&repository, &mut session, "read (\"temp\",_)"),
+        "(\"temp\",21)"
+    );
+    repository.sweep_expirations(later(61));
+    assert_eq!(
+        send(&repository, &mut session, "read (\"temp\",_)"),
+        "ERROR - No matching tuple could be found."
+    );
+    assert_eq!(
+        send(&repository, &mut session, "read (\"wind\",_)"),
+        "(\"wind\",3)"
+    );
+}
+
+#[test]
+fn test_ttl_of_a_tuple_written_again() {
+    let repository = repository();
+    let mut session = session(&["admin"]);
+    send(&repository, &mut session, "attach sensors");
+
+    // The tuple written again after being taken does not expire with the first one.
+    send(&repository, &mut session, "out ttl=1 (\"temp\",21)");
+    assert_eq!(
+        send(&repository, &mut session, "in (\"temp\",_)"),
+        "(\"temp\",21)"
+    );
+    send(&repository, &mut session, "out (\"temp\",21)");
+    repository.sweep_expirations(later(2));
+    assert_eq!(
+        send(&repository, &mut session, "read (\"temp\",_)"),
+        "(\"temp\",21)"
+    );
+
+    // Nor with the first deadline when written again with a longer time-to-live.
+    send(&repository, &mut session, "out ttl=1 (\"wind\",3)");
+    send(&repository, &mut session, "in (\"wind\",_)");
+    send(&repository, &mut session, "out ttl=60 (\"wind\",3)");
+    repository.sweep_expirations(later(2));
+    assert_eq!(
+        send(&repository, &mut session, "read (\"wind\",_)"),
+        "(\"wind\",3)"
+    );
+    repository.sweep_expirations(later(61));
+    assert_eq!(
+        send(&repository, &mut session, "read (\"wind\",_)"),
+        "ERROR - No matching tuple could be found."
+    );
+}
+
+#[test]
+fn test_parked_tuple_written_once_expired() {
+    let repository = repository();
+    repository
+        .add_tuple_space(
+            String::from("buffer"),
+            vec![String::from("admin")],
+            StoreKind::default(),
+            SpaceLimits::new(
+                Some(1),
+                None,
+                OverflowPolicy::Block(Duration::from_secs(3600)),
+            ),
+        )
+        .unwrap();
+    let mut session = session(&["admin"]);
+    send(&repository, &mut session, "attach buffer");
+    send(&repository, &mut session, "out ttl=60 (\"temp\",21)");
+    assert_eq!(
+        send(&repository, &mut session, "out (\"temp\",22)"),
+        "The tuple space is full, the tuple waits for room"
+    );
+
+    // The expired tuple makes room for the parked one.
+    repository.sweep_expirations(later(61));
+    assert_eq!(
+        send(&repository, &mut session, "read (\"temp\",_)"),
+        "(\"temp\",22)"
+    );
+}
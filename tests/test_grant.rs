@@ -0,0 +1,109 @@
+mod common;
+
+use std::time::{Duration, Instant, SystemTime};
+
+use common::{attributes, repository_with};
+use rustupolis_server::repository::Repository;
+
+fn repository() -> Repository {
+    repository_with(&[("sensors/temp", &["admin"]), ("sensors/wind", &["admin"])])
+}
+
+#[test]
+fn test_expiring_grant() {
+    let repository = repository();
+    let expiration = SystemTime::now() + Duration::from_secs(3600);
+    let id = repository.grant("sensors/temp", "out", "technician", Some(expiration), false);
+    let technician = attributes(&["technician"]);
+    assert!(repository.check_permission("out", &technician, Some("sensors/temp")));
+    assert!(!repository.check_permission("read", &technician, Some("sensors/temp")));
+    assert!(!repository.check_permission("out", &technician, Some("sensors/wind")));
+    let grants = repository.tuple_space_grants("sensors/temp");
+    assert_eq!(grants.len(), 1);
+    assert_eq!(grants[0].id(), id);
+    assert_eq!(grants[0].expiration(), Some(expiration));
+
+    // A permanent permission of the same attribute outlives the grant.
+    repository.add_permission(&String::from("operator"), "out", "sensors/temp");
+    repository.grant("sensors/temp", "out", "operator", Some(expiration), false);
+
+    repository.sweep_expirations(Instant::now() + Duration::from_secs(7200));
+    assert!(!repository.check_permission("out", &technician, Some("sensors/temp")));
+    assert!(repository.check_permission("out", &attributes(&["operator"]), Some("sensors/temp")));
+
+    // The grants are only listed until they lapse.
+    repository.grant(
+        "sensors/wind",
+        "out",
+        "intern",
+        Some(SystemTime::now()),
+        false,
+    );
+    assert!(repository.tuple_space_grants("sensors/wind").is_empty());
+}
+
+#[test]
+fn test_delegation() {
+    let repository = repository();
+    let hour = SystemTime::now() + Duration::from_secs(3600);
+    let minute = SystemTime::now() + Duration::from_secs(60);
+    let lead = attributes(&["lead"]);
+    let technician = attributes(&["technician"]);
+    let parent = repository.grant("sensors", "out", "lead", Some(hour), true);
+    assert!(repository.check_permission("out", &lead, Some("sensors/wind")));
+
+    // Narrower grants only: the same action, below the namespace, lapsing no later.
+    let child = repository
+        .delegate(
+            &lead,
+            "sensors/temp",
+            "out",
+            "technician",
+            Some(minute),
+            false,
+        )
+        .unwrap();
+    assert!(repository.check_permission("out", &technician, Some("sensors/temp")));
+    assert!(!repository.check_permission("out", &technician, Some("sensors/wind")));
+    assert_eq!(
+        repository.tuple_space_grants("sensors/temp")[0].parent(),
+        Some(parent)
+    );
+    let later = Some(hour + Duration::from_secs(1));
+    assert!(repository
+        .delegate(&lead, "sensors/temp", "out", "technician", later, false)
+        .is_err());
+    assert!(repository
+        .delegate(&lead, "sensors/temp", "out", "technician", None, false)
+        .is_err());
+    assert!(repository
+        .delegate(
+            &lead,
+            "sensors/temp",
+            "read",
+            "technician",
+            Some(minute),
+            false
+        )
+        .is_err());
+    assert!(repository
+        .delegate(&lead, "other", "out", "technician", Some(minute), false)
+        .is_err());
+    // A grant which is not delegable cannot be passed on.
+    assert!(repository
+        .delegate(
+            &technician,
+            "sensors/temp",
+            "out",
+            "intern",
+            Some(minute),
+            false
+        )
+        .is_err());
+
+    // Revoking a grant revokes those delegated from it.
+    assert!(repository.revoke_grant(parent));
+    assert!(!repository.revoke_grant(child));
+    assert!(!repository.check_permission("out", &lead, Some("sensors/wind")));
+    assert!(!repository.check_permission("out", &technician, Some("sensors/temp")));
+}
@@ -0,0 +1,49 @@
+use rustupolis::tuple;
+use rustupolis::tuple::E;
+use rustupolis_server::indexed_store::IndexedStore;
+use rustupolis_server::store::TupleStore;
+
+#[test]
+fn match_by_key() {
+    let mut store = IndexedStore::new(1);
+    store
+        .out(tuple![E::str("temp"), E::I(1), E::D(20.5)])
+        .unwrap();
+    store
+        .out(tuple![E::str("temp"), E::I(2), E::D(21.5)])
+        .unwrap();
+    store
+        .out(tuple![E::str("temp"), E::D(3.0), E::D(22.5)])
+        .unwrap();
+    assert_eq!(store.len(), 3);
+    assert_eq!(
+        store.rdp(&tuple![E::Any, E::I(2), E::Any]),
+        Some(tuple![E::str("temp"), E::I(2), E::D(21.5)])
+    );
+    // Floats are not keys, but still match with a tolerance.
+    assert_eq!(
+        store.inp(&tuple![E::Any, E::D(3.0001), E::Any]),
+        Some(tuple![E::str("temp"), E::D(3.0), E::D(22.5)])
+    );
+    assert_eq!(store.len(), 2);
+}
+
+#[test]
+fn match_by_arity() {
+    let mut store = IndexedStore::default();
+    store.out(tuple![E::str("state"), E::str("on")]).unwrap();
+    assert_eq!(store.rdp(&tuple![E::Any]), None);
+    assert_eq!(
+        store.inp(&tuple![E::Any, E::Any]),
+        Some(tuple![E::str("state"), E::str("on")])
+    );
+    assert!(store.is_empty());
+    assert_eq!(store.inp(&tuple![E::Any, E::Any]), None);
+}
+
+#[test]
+fn reject_undefined_tuple() {
+    let mut store = IndexedStore::default();
+    assert!(store.out(tuple![E::str("temp"), E::Any]).is_err());
+    assert!(store.is_empty());
+}
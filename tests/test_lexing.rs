@@ -0,0 +1,67 @@
+use proptest::prelude::*;
+use rustupolis::tuple;
+use rustupolis::tuple::{Tuple, E};
+use rustupolis_server::lexing::{format_tuple, format_tuples, parse_tuples};
+
+fn element() -> impl Strategy<Value = E> {
+    let leaf = prop_oneof![
+        any::<i32>().prop_map(E::I),
+        (proptest::num::f64::NORMAL
+            | proptest::num::f64::SUBNORMAL
+            | proptest::num::f64::ZERO
+            | proptest::num::f64::INFINITE)
+            .prop_map(E::D),
+        any::<String>().prop_map(E::S),
+        Just(E::Any),
+        Just(E::None),
+    ];
+    leaf.prop_recursive(4, 64, 8, |inner| {
+        prop::collection::vec(inner, 0..8).prop_map(|elements| E::T(Tuple::from_vec(elements)))
+    })
+}
+
+fn tuple() -> impl Strategy<Value = Tuple> {
+    prop::collection::vec(element(), 0..8).prop_map(Tuple::from_vec)
+}
+
+proptest! {
+    #[test]
+    fn test_round_trip(tuple in tuple()) {
+        prop_assert_eq!(parse_tuples(&format_tuple(&tuple)), Ok(vec![tuple]));
+    }
+
+    #[test]
+    fn test_round_trip_list(tuples in prop::collection::vec(tuple(), 0..8)) {
+        prop_assert_eq!(parse_tuples(&format_tuples(&tuples)), Ok(tuples));
+    }
+}
+
+#[test]
+fn test_format() {
+    let tuple = tuple![
+        E::str("say \"hi\""),
+        E::I(-3),
+        E::D(21.0),
+        E::T(tuple![E::Any, E::str("été")])
+    ];
+    assert_eq!(format_tuple(&tuple), r#"("say \"hi\"",-3,21.0,(_,"été"))"#);
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(parse_tuples("(\"unterminated)").is_err());
+    assert!(parse_tuples("(1,2").is_err());
+    assert!(parse_tuples("(99999999999)").is_err());
+    assert!(parse_tuples("21").is_err());
+}
+
+/// The `MAX_NESTING_DEPTH` of the server.
+const MAX_NESTING_DEPTH: usize = 64;
+
+#[test]
+fn test_nesting_depth() {
+    let nested = |depth: usize| format!("{}{}", "(".repeat(depth), ")".repeat(depth));
+    assert!(parse_tuples(&nested(MAX_NESTING_DEPTH)).is_ok());
+    assert!(parse_tuples(&nested(MAX_NESTING_DEPTH + 1)).is_err());
+    assert!(parse_tuples(&"(".repeat(1 << 20)).is_err());
+}
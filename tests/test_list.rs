@@ -0,0 +1,95 @@
+mod common;
+
+use std::time::SystemTime;
+
+use common::{repository_with, send, session};
+use rustupolis_server::repository::Repository;
+
+fn repository() -> Repository {
+    repository_with(&[
+        ("siteA/temp", &["siteA"]),
+        ("siteA/wind", &["siteA"]),
+        ("siteB/temp", &["siteB"]),
+    ])
+}
+
+#[test]
+fn test_list() {
+    let repository = repository();
+    let mut session = session(&["siteA", "siteB"]);
+    send(&repository, &mut session, "attach siteA/temp siteA");
+    send(&repository, &mut session, "out (1),(2)");
+
+    // Only the tuple spaces the attributes have a permission on are listed.
+    assert_eq!(
+        send(&repository, &mut session, "list siteA"),
+        "(\"siteA/temp\",2,(\"read\",\"in\",\"out\",\"delete\")),\
+         (\"siteA/wind\",0,(\"read\",\"in\",\"out\",\"delete\"))"
+    );
+    assert_eq!(
+        send(&repository, &mut session, "list --prefix=siteB siteB"),
+        "(\"siteB/temp\",0,(\"read\",\"in\",\"out\",\"delete\"))"
+    );
+    // Without attributes, those of the attachment are used.
+    assert_eq!(send(&repository, &mut session, "list --prefix=siteB"), "");
+    assert_eq!(
+        send(&repository, &mut session, "list --prefix=siteC siteA"),
+        ""
+    );
+    assert_eq!(
+        send(&repository, &mut session, "list --limit=1"),
+        "ERROR - Invalid option"
+    );
+    assert_eq!(
+        send(&repository, &mut session, "list siteC"),
+        "ERROR - No permission"
+    );
+}
+
+#[test]
+fn test_describe() {
+    let repository = repository();
+    let mut session = session(&["siteA"]);
+    send(&repository, &mut session, "attach siteA/temp");
+    send(&repository, &mut session, "out (1)");
+
+    // The size of a tuple holding one integer is 10 bytes.
+    assert_eq!(
+        send(&repository, &mut session, "describe siteA/temp siteA"),
+        "(\"siteA/temp\",1,10,(\"read\",\"in\",\"out\",\"delete\"))"
+    );
+    assert_eq!(
+        send(&repository, &mut session, "describe siteB/temp"),
+        "ERROR - Tuple space not found"
+    );
+    assert_eq!(
+        send(&repository, &mut session, "describe siteC/temp"),
+        "ERROR - Tuple space not found"
+    );
+    assert_eq!(
+        send(&repository, &mut session, "describe siteB/temp siteB"),
+        "ERROR - No permission"
+    );
+    assert_eq!(
+        send(&repository, &mut session, "describe"),
+        "ERROR - The request is malformed"
+    );
+}
+
+#[test]
+fn test_list_once_expired() {
+    let repository = repository();
+    let mut session = session(&["siteA"]);
+    send(&repository, &mut session, "attach siteA/temp siteA");
+    session.authenticate(vec![String::from("siteA")], Some(SystemTime::now()));
+
+    // The attachment does not outlive the credential.
+    assert_eq!(
+        send(&repository, &mut session, "list"),
+        "ERROR - No permission"
+    );
+    assert_eq!(
+        send(&repository, &mut session, "describe siteA/temp"),
+        "ERROR - No permission"
+    );
+}
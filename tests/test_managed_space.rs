@@ -0,0 +1,265 @@
+mod common;
+
+use common::{send, session};
+use rustupolis::tuple;
+use rustupolis::tuple::E;
+use rustupolis_server::managed_space::{ManagedSpace, OutError, OverflowPolicy, SpaceLimits};
+use rustupolis_server::provenance::Provenance;
+use rustupolis_server::repository::Repository;
+use rustupolis_server::store::{MemoryStore, StoreKind};
+use std::time::Duration;
+
+#[test]
+fn reject_when_full() {
+    let mut space = ManagedSpace::new(
+        Box::new(MemoryStore::new()),
+        SpaceLimits::new(Some(1), None, OverflowPolicy::Reject),
+    );
+    assert_eq!(space.out(tuple![E::I(1)]), Ok(vec![]));
+    assert_eq!(space.out(tuple![E::I(2)]), Err(OutError::Full));
+    assert_eq!(space.take(tuple![E::Any]), Some(tuple![E::I(1)]));
+    assert_eq!(space.out(tuple![E::I(2)]), Ok(vec![]));
+    assert_eq!(space.len(), 1);
+}
+
+#[test]
+fn drop_oldest_when_full() {
+    let mut space = ManagedSpace::new(
+        Box::new(MemoryStore::new()),
+        SpaceLimits::new(Some(2), None, OverflowPolicy::DropOldest),
+    );
+    for value in 0..2 {
+        assert_eq!(space.out(tuple![E::I(value)]), Ok(vec![]));
+    }
+    for value in 2..4 {
+        assert_eq!(
+            space.out(tuple![E::I(value)]),
+            Ok(vec![tuple![E::I(value - 2)]])
+        );
+    }
+    assert_eq!(space.len(), 2);
+    assert_eq!(space.read(tuple![E::I(1)]), None);
+    assert_eq!(space.read(tuple![E::I(3)]), Some(tuple![E::I(3)]));
+}
+
+#[test]
+fn park_when_full() {
+    let mut space = ManagedSpace::new(
+        Box::new(MemoryStore::new()),
+        SpaceLimits::new(
+            Some(1),
+            None,
+            OverflowPolicy::Block(Duration::from_secs(60)),
+        ),
+    );
+    assert_eq!(space.out(tuple![E::I(1)]), Ok(vec![]));
+    assert_eq!(space.out(tuple![E::I(2)]), Err(OutError::Full));
+    assert!(space.park(tuple![E::I(2)], None, None));
+    assert!(space.park(tuple![E::I(3)], None, None));
+    assert!(space.unpark().is_empty());
+    assert_eq!(space.take(tuple![E::Any]), Some(tuple![E::I(1)]));
+    assert_eq!(space.unpark(), vec![(tuple![E::I(2)], None, None)]);
+    assert_eq!(space.read(tuple![E::Any]), Some(tuple![E::I(2)]));
+    assert_eq!(space.take(tuple![E::Any]), Some(tuple![E::I(2)]));
+    assert_eq!(space.unpark(), vec![(tuple![E::I(3)], None, None)]);
+
+    // Only a blocking policy parks tuples.
+    let mut space = ManagedSpace::new(
+        Box::new(MemoryStore::new()),
+        SpaceLimits::new(Some(1), None, OverflowPolicy::Reject),
+    );
+    assert!(!space.park(tuple![E::I(1)], None, None));
+}
+
+#[test]
+fn parked_tuples_written_once_taken() {
+    let repository = Repository::new("admin");
+    repository
+        .add_tuple_space(
+            String::from("sensors"),
+            vec![String::from("admin")],
+            StoreKind::default(),
+            SpaceLimits::new(
+                Some(1),
+                None,
+                OverflowPolicy::Block(Duration::from_secs(60)),
+            ),
+        )
+        .unwrap();
+    let mut session = session(&["admin"]);
+    send(&repository, &mut session, "attach sensors");
+    assert_eq!(
+        send(&repository, &mut session, "out (1)"),
+        "Successful request"
+    );
+    // The server answers at once rather than waiting for room.
+    assert_eq!(
+        send(&repository, &mut session, "out (2)"),
+        "The tuple space is full, the tuple waits for room"
+    );
+    assert_eq!(send(&repository, &mut session, "read (_)"), "(1)");
+    assert_eq!(send(&repository, &mut session, "in (_)"), "(1)");
+    assert_eq!(send(&repository, &mut session, "read (_)"), "(2)");
+}
+
+#[test]
+fn create_options() {
+    let repository = Repository::new("admin");
+    let mut admin = session(&["admin"]);
+    assert_eq!(
+        send(
+            &repository,
+            &mut admin,
+            "create admin sensors --max_tuples=1 --policy=reject site=A"
+        ),
+        "Successful request"
+    );
+    // The attributes may hold `=`, and start with `--` after the `--` word.
+    assert_eq!(
+        send(&repository, &mut admin, "create admin wind -- --site"),
+        "Successful request"
+    );
+    assert_eq!(
+        send(&repository, &mut admin, "create admin rain --policy=later"),
+        "ERROR - Invalid option"
+    );
+    for request in ["create admin", "delete admin"] {
+        assert_eq!(
+            send(&repository, &mut admin, request),
+            "ERROR - The request is malformed"
+        );
+    }
+
+    let mut session = session(&["site=A", "--site"]);
+    assert_eq!(
+        send(&repository, &mut session, "attach sensors site=A"),
+        "Tuple space attached"
+    );
+    send(&repository, &mut session, "out (1)");
+    assert_eq!(
+        send(&repository, &mut session, "out (2)"),
+        "ERROR - The tuple space is full"
+    );
+    assert_eq!(
+        send(&repository, &mut session, "attach wind --site"),
+        "Tuple space attached"
+    );
+}
+
+#[test]
+fn byte_limit() {
+    // A tuple holding one integer takes 10 bytes: tag and count, then tag and value.
+    let mut space = ManagedSpace::new(
+        Box::new(MemoryStore::new()),
+        SpaceLimits::new(None, Some(25), OverflowPolicy::Reject),
+    );
+    assert_eq!(space.out(tuple![E::I(1)]), Ok(vec![]));
+    assert_eq!(space.out(tuple![E::I(2)]), Ok(vec![]));
+    assert_eq!(space.out(tuple![E::I(3)]), Err(OutError::Full));
+    assert_eq!(space.size(), 20);
+}
+
+#[test]
+fn limits_from_options() {
+    let limits = SpaceLimits::new(
+        Some(10),
+        None,
+        OverflowPolicy::Block(Duration::from_secs(2)),
+    );
+    let options = limits.to_options();
+    let options: Vec<&str> = options.iter().map(String::as_str).collect();
+    assert_eq!(SpaceLimits::from_options(&options), Some(limits));
+    assert_eq!(SpaceLimits::from_options(&["policy=later"]), None);
+    assert_eq!(SpaceLimits::from_options(&["max_tuples=-1"]), None);
+}
+
+#[test]
+fn provenance_and_visibility() {
+    let mut space = ManagedSpace::new(Box::new(MemoryStore::new()), SpaceLimits::unlimited());
+    let secret = tuple![E::str("temp"), E::I(1)];
+    let public = tuple![E::str("temp"), E::I(2)];
+    let unknown = tuple![E::str("temp"), E::I(3)];
+    let author = vec![String::from("sensor")];
+    let provenance = Provenance::new(author.clone(), Some(vec![String::from("operator")]));
+    assert_eq!(
+        space.out_with_provenance(secret.clone(), provenance.clone()),
+        Ok(vec![])
+    );
+    let public_provenance = Provenance::new(author, None);
+    assert_eq!(
+        space.out_with_provenance(public.clone(), public_provenance.clone()),
+        Ok(vec![])
+    );
+    assert_eq!(space.out(unknown.clone()), Ok(vec![]));
+
+    // Writing the tuple again keeps the provenance of the first write.
+    assert_eq!(
+        space.out_with_provenance(secret.clone(), public_provenance),
+        Ok(vec![])
+    );
+    assert_eq!(space.provenance(&secret), Some(&provenance));
+    assert_eq!(space.provenance(&unknown), None);
+
+    let reader = [String::from("reader")];
+    let operator = [String::from("operator")];
+    let template = tuple![E::str("temp"), E::Any];
+    assert_eq!(space.read_all_visible(&template, &reader).len(), 2);
+    assert_eq!(space.read_all_visible(&template, &operator).len(), 3);
+    assert_eq!(space.read_visible(secret.clone(), &reader), None);
+    assert_eq!(space.take_visible(secret.clone(), &reader), None);
+    assert_eq!(
+        space.take_visible(secret.clone(), &operator),
+        Some(secret.clone())
+    );
+    assert_eq!(space.provenance(&secret), None);
+    assert_eq!(space.len(), 2);
+}
+
+#[test]
+fn drop_oldest_with_provenance() {
+    let mut space = ManagedSpace::new(
+        Box::new(MemoryStore::new()),
+        SpaceLimits::new(Some(1), None, OverflowPolicy::DropOldest),
+    );
+    let secret = tuple![E::str("temp"), E::I(1)];
+    let provenance = Provenance::new(
+        vec![String::from("sensor")],
+        Some(vec![String::from("operator")]),
+    );
+    assert_eq!(
+        space.write(secret.clone(), Some(provenance.clone())),
+        Ok(vec![])
+    );
+    assert_eq!(
+        space.write(tuple![E::str("temp"), E::I(2)], None),
+        Ok(vec![(secret.clone(), Some(provenance.clone()))])
+    );
+
+    // Written back, the dropped tuple is only visible to its readers again.
+    space.take(tuple![E::str("temp"), E::Any]);
+    assert_eq!(space.write(secret.clone(), Some(provenance)), Ok(vec![]));
+    let reader = [String::from("reader")];
+    assert_eq!(space.read_visible(secret.clone(), &reader), None);
+}
+
+#[test]
+fn refuse_acl_on_disk_store() {
+    let repository = Repository::new("admin");
+    let mut session = session(&["admin"]);
+    let name = format!("acl_{}", std::process::id());
+    send(
+        &repository,
+        &mut session,
+        &format!("create admin {} --store=disk admin", name),
+    );
+    send(&repository, &mut session, &format!("attach {}", name));
+    assert_eq!(
+        send(&repository, &mut session, "out acl=operator (\"temp\",1)"),
+        "ERROR - Disk stores do not keep access control lists"
+    );
+    assert_eq!(
+        send(&repository, &mut session, "out (\"temp\",1)"),
+        "Successful request"
+    );
+    repository.remove_tuple_space(&name);
+}
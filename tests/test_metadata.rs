@@ -0,0 +1,48 @@
+use rustupolis::tuple;
+use rustupolis::tuple::E;
+use rustupolis_server::managed_space::{OverflowPolicy, SpaceLimits};
+use rustupolis_server::metadata::SpaceMetadata;
+use rustupolis_server::repository::Repository;
+use rustupolis_server::store::StoreKind;
+
+#[test]
+fn record_metadata() {
+    let repository = Repository::new("admin");
+    let limits = SpaceLimits::new(Some(10), None, OverflowPolicy::DropOldest);
+    let metadata = SpaceMetadata::new(
+        Some(String::from("admin")),
+        String::from("Readings"),
+        StoreKind::Ring(20),
+        limits,
+    );
+    repository
+        .add_tuple_space_with_metadata(
+            String::from("DATA"),
+            vec![String::from("admin")],
+            metadata.clone(),
+        )
+        .unwrap();
+    assert_eq!(repository.tuple_space_metadata("DATA"), Some(metadata));
+
+    assert!(repository.set_tuple_space_description("DATA", String::from("Kitchen readings")));
+    assert!(!repository.set_tuple_space_description("MISSING", String::new()));
+    let metadata = repository.tuple_space_metadata("DATA").unwrap();
+    assert_eq!(metadata.description(), "Kitchen readings");
+    assert_eq!(metadata.owner(), Some("admin"));
+
+    let description = metadata.to_tuple("DATA");
+    let options = tuple![
+        E::str("store=ring"),
+        E::str("capacity=20"),
+        E::str("max_tuples=10"),
+        E::str("policy=drop_oldest")
+    ];
+    assert_eq!(description.first(), &E::str("DATA"));
+    assert_eq!(
+        description.rest().rest().rest().rest().first(),
+        &E::T(options)
+    );
+
+    repository.remove_tuple_space("DATA");
+    assert_eq!(repository.tuple_space_metadata("DATA"), None);
+}
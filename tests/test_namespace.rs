@@ -0,0 +1,95 @@
+use rustupolis_server::managed_space::SpaceLimits;
+use rustupolis_server::repository::Repository;
+use rustupolis_server::store::StoreKind;
+
+fn add(repository: &Repository, name: &str, attributes: &[&str]) -> Result<(), String> {
+    repository.add_tuple_space(
+        String::from(name),
+        attributes
+            .iter()
+            .map(|attribute| String::from(*attribute))
+            .collect(),
+        StoreKind::default(),
+        SpaceLimits::unlimited(),
+    )
+}
+
+#[test]
+fn inherit_permissions() {
+    let repository = Repository::new("admin");
+    repository.add_permission_list(vec![String::from("staff")], "siteA");
+    add(&repository, "siteA/floor2/temp", &["floor2"]).unwrap();
+    add(&repository, "siteAB", &["other"]).unwrap();
+
+    let staff = vec![String::from("staff")];
+    let floor2 = vec![String::from("floor2")];
+    assert!(repository.check_permission("read", &staff, Some("siteA/floor2/temp")));
+    assert!(repository.check_permission("read", &floor2, Some("siteA/floor2/temp")));
+    assert!(!repository.check_permission("read", &floor2, Some("siteA")));
+    assert!(!repository.check_permission("read", &staff, Some("siteAB")));
+
+    assert!(add(&repository, "siteA//temp", &["staff"]).is_err());
+    assert!(add(&repository, "/siteA", &["staff"]).is_err());
+}
+
+#[test]
+fn remove_subtree() {
+    let repository = Repository::new("admin");
+    for name in ["siteA", "siteA/floor2", "siteA/floor2/temp", "siteAB"] {
+        add(&repository, name, &["admin"]).unwrap();
+    }
+    let mut removed = repository.remove_namespace("siteA/floor2");
+    removed.sort();
+    assert_eq!(removed, vec!["siteA/floor2", "siteA/floor2/temp"]);
+    assert_eq!(repository.remove_namespace("siteA"), vec!["siteA"]);
+    assert!(repository.tuple_space_metadata("siteAB").is_some());
+}
+
+#[test]
+fn purge_permissions() {
+    let repository = Repository::new("admin");
+    add(&repository, "DATA", &["old"]).unwrap();
+    repository.remove_tuple_space("DATA");
+    add(&repository, "DATA", &["new"]).unwrap();
+    let old = vec![String::from("old")];
+    let new = vec![String::from("new")];
+    assert!(!repository.check_permission("read", &old, Some("DATA")));
+    assert!(repository.check_permission("read", &new, Some("DATA")));
+}
+
+#[test]
+fn keep_namespace_permissions_of_remaining_tuple_spaces() {
+    let repository = Repository::new("admin");
+    add(&repository, "siteA", &["staff"]).unwrap();
+    add(&repository, "siteA/temp", &["floor2"]).unwrap();
+    let staff = vec![String::from("staff")];
+    repository.remove_tuple_space("siteA");
+    assert!(repository.check_permission("read", &staff, Some("siteA/temp")));
+
+    add(&repository, "siteA", &["staff"]).unwrap();
+    repository.remove_namespace("siteA");
+    add(&repository, "siteA/temp", &["floor2"]).unwrap();
+    assert!(!repository.check_permission("read", &staff, Some("siteA/temp")));
+}
+
+#[test]
+fn remove_disk_store_log() {
+    let repository = Repository::new("admin");
+    let name = format!("removed_{}", std::process::id());
+    let store = StoreKind::from_options(&["store=disk"], &name).unwrap();
+    let path = match &store {
+        StoreKind::Disk(path) => path.clone(),
+        _ => unreachable!(),
+    };
+    repository
+        .add_tuple_space(
+            name.clone(),
+            vec![String::from("admin")],
+            store,
+            SpaceLimits::unlimited(),
+        )
+        .unwrap();
+    assert!(path.exists());
+    repository.remove_tuple_space(&name);
+    assert!(!path.exists());
+}
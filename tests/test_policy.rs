@@ -0,0 +1,151 @@
+mod common;
+
+use common::{attributes, send, session};
+use rustupolis_server::managed_space::SpaceLimits;
+use rustupolis_server::policy::{Effect, Policy};
+use rustupolis_server::repository::Repository;
+use rustupolis_server::store::StoreKind;
+
+#[test]
+fn test_parse() {
+    let policy = Policy::parse("staff and not (contractor or intern)").unwrap();
+    assert!(policy.evaluate(&attributes(&["staff"])));
+    assert!(!policy.evaluate(&attributes(&["staff", "intern"])));
+    assert!(!policy.evaluate(&attributes(&["contractor"])));
+
+    // `not` binds tighter than `and`, which binds tighter than `or`.
+    let policy = Policy::parse("a or b and not c").unwrap();
+    assert_eq!(policy, Policy::parse("a or (b and (not c))").unwrap());
+    assert!(policy.evaluate(&attributes(&["a", "c"])));
+    assert!(!policy.evaluate(&attributes(&["b", "c"])));
+
+    for expression in [
+        "staff and not (contractor or intern)",
+        "(a or b) and c",
+        "a and (b and c)",
+        "not not a or (b or c)",
+    ] {
+        let policy = Policy::parse(expression).unwrap();
+        assert_eq!(Policy::parse(&policy.to_string()), Ok(policy));
+    }
+
+    for expression in ["", "a and", "(a or b", "a b", "or a", "a)"] {
+        assert!(Policy::parse(expression).is_err(), "{}", expression);
+    }
+}
+
+#[test]
+fn test_repository_policy() {
+    let repository = Repository::new("admin");
+    for name in ["siteA/temp", "siteB/temp"] {
+        repository
+            .add_tuple_space(
+                String::from(name),
+                attributes(&["staff"]),
+                StoreKind::default(),
+                SpaceLimits::unlimited(),
+            )
+            .unwrap();
+    }
+    let operator = attributes(&["siteA", "operator"]);
+    assert!(!repository.check_permission("out", &operator, Some("siteA/temp")));
+
+    // Both attributes are needed, on every tuple space of the namespace.
+    let allow = repository
+        .add_policy(Some("siteA"), "out", Effect::Allow, "siteA and operator")
+        .unwrap();
+    assert!(repository.check_permission("out", &operator, Some("siteA/temp")));
+    assert!(!repository.check_permission("read", &operator, Some("siteA/temp")));
+    assert!(!repository.check_permission("out", &operator, Some("siteB/temp")));
+    assert!(!repository.check_permission("out", &attributes(&["operator"]), Some("siteA/temp")));
+
+    // A deny takes precedence over the policies and the permissions allowing the action.
+    repository
+        .add_policy(Some("siteA/temp"), "out", Effect::Deny, "contractor")
+        .unwrap();
+    let contractor = attributes(&["siteA", "operator", "staff", "contractor"]);
+    assert!(!repository.check_permission("out", &contractor, Some("siteA/temp")));
+    assert!(repository.check_permission("read", &contractor, Some("siteA/temp")));
+    assert!(repository.check_permission("out", &contractor, Some("siteB/temp")));
+
+    // The policies are evaluated against the attributes implied by the roles.
+    repository.add_role("agency", attributes(&["contractor"]));
+    assert!(!repository.check_permission(
+        "out",
+        &attributes(&["staff", "agency"]),
+        Some("siteA/temp")
+    ));
+
+    let policies = repository.tuple_space_policies(Some("siteA/temp"));
+    assert_eq!(policies.len(), 1);
+    assert_eq!(policies[0].1, "out");
+    assert_eq!(policies[0].2, Effect::Deny);
+
+    assert!(repository.remove_policy(allow));
+    assert!(!repository.remove_policy(allow));
+    assert!(!repository.check_permission("out", &operator, Some("siteA/temp")));
+
+    // Policies on no tuple space apply to `create`.
+    assert!(repository.check_permission("create", &attributes(&["admin"]), None));
+    repository
+        .add_policy(None, "create", Effect::Deny, "admin and not operator")
+        .unwrap();
+    assert!(!repository.check_permission("create", &attributes(&["admin"]), None));
+    assert!(repository.check_permission("create", &attributes(&["admin", "operator"]), None));
+
+    assert!(repository
+        .add_policy(Some("siteA"), "read", Effect::Allow, "(siteA")
+        .is_err());
+
+    repository.remove_tuple_space("siteA/temp");
+    assert!(repository
+        .tuple_space_policies(Some("siteA/temp"))
+        .is_empty());
+}
+
+#[test]
+fn test_narrowed_attach() {
+    let repository = Repository::new("admin");
+    for name in ["siteA/temp", "siteB/temp"] {
+        repository
+            .add_tuple_space(
+                String::from(name),
+                attributes(&["staff"]),
+                StoreKind::default(),
+                SpaceLimits::unlimited(),
+            )
+            .unwrap();
+    }
+    repository
+        .add_policy(Some("siteA"), "out", Effect::Deny, "contractor")
+        .unwrap();
+    repository
+        .add_policy(Some("siteB"), "out", Effect::Allow, "siteB and not intern")
+        .unwrap();
+    repository.add_role("agency", attributes(&["contractor"]));
+
+    // Leaving the denied attribute out of the attachment does not lift the policy.
+    let mut contractor = session(&["staff", "agency"]);
+    send(&repository, &mut contractor, "attach siteA/temp staff");
+    assert_eq!(
+        send(&repository, &mut contractor, "out (1)"),
+        "ERROR - No permission"
+    );
+    assert_eq!(
+        send(&repository, &mut contractor, "read (1)"),
+        "ERROR - No matching tuple could be found."
+    );
+
+    // Neither does leaving out the attribute of a `not` term.
+    let mut intern = session(&["siteB", "intern"]);
+    assert_eq!(
+        send(&repository, &mut intern, "attach siteB/temp siteB"),
+        "ERROR - No permission"
+    );
+    let mut member = session(&["siteB"]);
+    send(&repository, &mut member, "attach siteB/temp siteB");
+    assert_eq!(
+        send(&repository, &mut member, "out (1)"),
+        "Successful request"
+    );
+}
@@ -0,0 +1,77 @@
+use rustupolis::tuple;
+use rustupolis::tuple::E;
+use rustupolis_server::reaction::{Reaction, Reactions};
+
+#[test]
+fn react_to_matching_tuples() {
+    let reactions = Reactions::default();
+    reactions.add_rule("DATA", tuple![E::str("temp"), E::Any], |tuple| {
+        vec![Reaction::Transform(tuple![tuple.first().clone(), E::I(0)])]
+    });
+    reactions.add_rule("DATA", tuple![E::str("temp"), E::I(0)], |tuple| {
+        vec![Reaction::Emit(String::from("LOG"), vec![tuple.clone()])]
+    });
+
+    let (tuple, emitted) = reactions.react("DATA", tuple![E::str("temp"), E::I(21)]);
+    assert_eq!(tuple, Some(tuple![E::str("temp"), E::I(0)]));
+    assert_eq!(
+        emitted,
+        vec![(String::from("LOG"), tuple![E::str("temp"), E::I(0)])]
+    );
+
+    // Rules only apply to their tuple space, and to tuples of the arity of their template.
+    let tuple = tuple![E::str("temp"), E::I(21), E::I(1)];
+    assert_eq!(
+        reactions.react("DATA", tuple.clone()),
+        (Some(tuple.clone()), vec![])
+    );
+    assert_eq!(
+        reactions.react("OTHER", tuple.clone()),
+        (Some(tuple), vec![])
+    );
+}
+
+#[test]
+fn drop_tuples() {
+    let reactions = Reactions::default();
+    let id = reactions.add_rule("DATA", tuple![E::str("noise"), E::Any], |tuple| {
+        vec![
+            Reaction::Drop,
+            Reaction::Emit(String::from("LOG"), vec![tuple.clone()]),
+        ]
+    });
+
+    let noise = tuple![E::str("noise"), E::I(1)];
+    assert_eq!(
+        reactions.react("DATA", noise.clone()),
+        (None, vec![(String::from("LOG"), noise.clone())])
+    );
+    assert!(reactions.remove_rule(id));
+    assert!(!reactions.remove_rule(id));
+    assert_eq!(
+        reactions.react("DATA", noise.clone()),
+        (Some(noise), vec![])
+    );
+}
+
+#[test]
+fn add_rules_from_an_action() {
+    let reactions = Reactions::default();
+    let inner = reactions.clone();
+    reactions.add_rule("DATA", tuple![E::str("watch"), E::Any], move |tuple| {
+        inner.add_rule("DATA", tuple![tuple.rest().first().clone(), E::Any], |_| {
+            vec![Reaction::Drop]
+        });
+        vec![]
+    });
+
+    let watch = tuple![E::str("watch"), E::str("noise")];
+    assert_eq!(
+        reactions.react("DATA", watch.clone()),
+        (Some(watch), vec![])
+    );
+    assert_eq!(
+        reactions.react("DATA", tuple![E::str("noise"), E::I(1)]),
+        (None, vec![])
+    );
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use common::{attributes, send, session};
+use rustupolis_server::managed_space::SpaceLimits;
+use rustupolis_server::repository::Repository;
+use rustupolis_server::store::StoreKind;
+
+#[test]
+fn test_hierarchy() {
+    let repository = Repository::new("admin");
+    repository
+        .add_tuple_space(
+            String::from("sensors"),
+            attributes(&["reader", "operator", "operator", "admin"]),
+            StoreKind::default(),
+            SpaceLimits::unlimited(),
+        )
+        .unwrap();
+    repository.add_role("admin", attributes(&["operator"]));
+    repository.add_role("operator", attributes(&["reader"]));
+
+    let admin = attributes(&["admin"]);
+    let operator = attributes(&["operator"]);
+    let reader = attributes(&["reader"]);
+    for action in ["read", "in", "out", "delete"] {
+        assert!(repository.check_permission(action, &admin, Some("sensors")));
+    }
+    assert!(repository.check_permission("read", &operator, Some("sensors")));
+    assert!(repository.check_permission("out", &operator, Some("sensors")));
+    assert!(!repository.check_permission("delete", &operator, Some("sensors")));
+    assert!(repository.check_permission("read", &reader, Some("sensors")));
+    assert!(!repository.check_permission("out", &reader, Some("sensors")));
+
+    assert!(repository.remove_role("operator"));
+    assert!(!repository.remove_role("operator"));
+    assert!(!repository.check_permission("read", &admin, Some("sensors")));
+    assert!(repository.check_permission("out", &admin, Some("sensors")));
+}
+
+#[test]
+fn test_role() {
+    let repository = Repository::new("admin");
+    for name in ["siteA/temp", "siteA/humidity", "siteB/temp"] {
+        repository
+            .add_tuple_space(
+                String::from(name),
+                attributes(&["admin"]),
+                StoreKind::default(),
+                SpaceLimits::unlimited(),
+            )
+            .unwrap();
+    }
+    repository.add_permission_list(attributes(&["siteA"]), "siteA");
+    repository.add_permission_list(attributes(&["siteB"]), "siteB");
+
+    // One call gives the team access to every tuple space of both sites.
+    repository.add_role("team", attributes(&["siteA", "siteB"]));
+    repository.add_role("team", attributes(&["siteA"]));
+    assert_eq!(
+        repository.role_attributes("team"),
+        attributes(&["siteA", "siteB"])
+    );
+    let team = attributes(&["team"]);
+    for name in ["siteA/temp", "siteA/humidity", "siteB/temp"] {
+        assert!(repository.check_permission("read", &team, Some(name)));
+    }
+    assert!(!repository.check_permission("create", &team, None));
+
+    // The roles may imply each other.
+    repository.add_role("siteA", attributes(&["team"]));
+    assert!(repository.check_permission("read", &attributes(&["siteA"]), Some("siteB/temp")));
+}
+
+#[test]
+fn test_attach_with_roles() {
+    let repository = Repository::new("admin");
+    repository
+        .add_tuple_space(
+            String::from("sensors"),
+            attributes(&["reader", "reader", "operator", "operator"]),
+            StoreKind::default(),
+            SpaceLimits::unlimited(),
+        )
+        .unwrap();
+    repository.add_role("admin", attributes(&["operator"]));
+    let mut session = session(&["admin"]);
+
+    // The attributes claimed must be held, directly or through a role.
+    assert_eq!(
+        send(&repository, &mut session, "attach sensors reader"),
+        "ERROR - No permission"
+    );
+    assert!(session.tuple_space().is_none());
+    assert_eq!(
+        send(&repository, &mut session, "attach sensors operator"),
+        "Tuple space attached"
+    );
+    assert_eq!(
+        session.tuple_space().unwrap().attributes(),
+        &attributes(&["operator"])
+    );
+}
@@ -0,0 +1,52 @@
+use rustupolis::tuple;
+use rustupolis::tuple::E;
+use rustupolis_server::store::StoreKind;
+use std::fs;
+
+#[test]
+fn ring_drops_oldest() {
+    let mut store = StoreKind::Ring(2).open().unwrap();
+    assert_eq!(store.out(tuple![E::I(1)]), Ok(None));
+    assert_eq!(store.out(tuple![E::I(2)]), Ok(None));
+    assert_eq!(store.out(tuple![E::I(3)]), Ok(Some(tuple![E::I(1)])));
+    assert_eq!(store.tuples(), vec![tuple![E::I(2)], tuple![E::I(3)]]);
+}
+
+#[test]
+fn disk_replays_log() {
+    let path = std::env::temp_dir().join(format!("test_store_{}.tuples", std::process::id()));
+    let _ = fs::remove_file(&path);
+    {
+        let mut store = StoreKind::Disk(path.clone()).open().unwrap();
+        store.out(tuple![E::str("temp"), E::I(21)]).unwrap();
+        store.out(tuple![E::str("temp"), E::I(23)]).unwrap();
+        assert_eq!(
+            store.inp(&tuple![E::str("temp"), E::Any]),
+            Some(tuple![E::str("temp"), E::I(21)])
+        );
+    }
+    let mut store = StoreKind::Disk(path.clone()).open().unwrap();
+    assert_eq!(store.len(), 1);
+    assert_eq!(
+        store.rdp(&tuple![E::str("temp"), E::Any]),
+        Some(tuple![E::str("temp"), E::I(23)])
+    );
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn store_from_options() {
+    assert_eq!(
+        StoreKind::from_options(&["store=ring", "capacity=10"], "DATA"),
+        Some(StoreKind::Ring(10))
+    );
+    assert_eq!(
+        StoreKind::from_options(&["store=indexed", "key=1"], "DATA"),
+        Some(StoreKind::Indexed(1))
+    );
+    assert_eq!(
+        StoreKind::from_options(&["store=disk"], "a/../b"),
+        Some(StoreKind::Disk("tuple_spaces/a%2F%2E%2E%2Fb.tuples".into()))
+    );
+    assert_eq!(StoreKind::from_options(&["store=ring"], "DATA"), None);
+}
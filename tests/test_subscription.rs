@@ -0,0 +1,171 @@
+mod common;
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use aes_gcm::aead::{Aead, NewAead};
+use aes_gcm::{Aes128Gcm, Key, Nonce};
+use common::KEY;
+use rustupolis_server::managed_space::SpaceLimits;
+use rustupolis_server::repository::Repository;
+use rustupolis_server::server::{Protocol, Server};
+use rustupolis_server::store::StoreKind;
+
+fn cipher() -> Aes128Gcm {
+    Aes128Gcm::new(Key::from_slice(KEY.as_bytes()))
+}
+
+/// Starts a TCP server on a free port of the loopback interface, serving a repository with a
+/// tuple space which the `admin` token may use. Returns the repository and the port.
+fn start_server() -> (&'static Repository, String) {
+    let repository: &'static Repository = Box::leak(Box::new(Repository::new("admin")));
+    repository
+        .add_tuple_space(
+            String::from("sensors"),
+            vec![String::from("admin")],
+            StoreKind::default(),
+            SpaceLimits::unlimited(),
+        )
+        .unwrap();
+    repository.add_credential("token", vec![String::from("admin")]);
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+        .to_string();
+    let server_port = port.clone();
+    thread::spawn(move || {
+        let ip_address = String::from("127.0.0.1");
+        Server::new(Protocol::TCP, &ip_address, &server_port, repository, KEY).start_server()
+    });
+    (repository, port)
+}
+
+/// Connects to the server once it listens, and reads its greeting.
+fn connect(port: &str) -> TcpStream {
+    for _ in 0..100 {
+        if let Ok(mut stream) = TcpStream::connect(format!("127.0.0.1:{}", port)) {
+            let mut greeting = [0; 9];
+            stream.read_exact(&mut greeting).unwrap();
+            assert_eq!(&greeting, b"Connected");
+            stream
+                .set_read_timeout(Some(Duration::from_secs(10)))
+                .unwrap();
+            return stream;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    panic!("the server does not listen");
+}
+
+/// Reads a message of the server: its length as a big-endian `u32`, then the encrypted text.
+fn read_message(stream: &mut TcpStream) -> String {
+    let mut length = [0; 4];
+    stream.read_exact(&mut length).unwrap();
+    let mut message = vec![0; u32::from_be_bytes(length) as usize];
+    stream.read_exact(&mut message).unwrap();
+    let text = cipher()
+        .decrypt(Nonce::from_slice(b"unique nonce"), message.as_ref())
+        .unwrap();
+    String::from_utf8(text).unwrap()
+}
+
+fn request(stream: &mut TcpStream, request: &str) -> String {
+    let request = cipher()
+        .encrypt(Nonce::from_slice(b"unique nonce"), request.as_bytes())
+        .unwrap();
+    stream.write_all(&request).unwrap();
+    read_message(stream)
+}
+
+fn attach(port: &str) -> TcpStream {
+    attach_with(port, "token")
+}
+
+fn attach_with(port: &str, token: &str) -> TcpStream {
+    let mut stream = connect(port);
+    assert_eq!(
+        request(&mut stream, &format!("auth {}", token)),
+        "Authenticated"
+    );
+    assert_eq!(
+        request(&mut stream, "attach sensors"),
+        "Tuple space attached"
+    );
+    stream
+}
+
+#[test]
+fn test_notification() {
+    let (_, port) = start_server();
+    let mut subscriber = attach(&port);
+    let mut producer = attach(&port);
+    assert_eq!(
+        request(&mut subscriber, "subscribe 7 (\"temp\",_)"),
+        "Successful request"
+    );
+    assert_eq!(
+        request(&mut producer, "out (\"wind\",3),(\"temp\",21)"),
+        "Successful request"
+    );
+    // Only the tuple matching the template is pushed, framed like the responses.
+    assert_eq!(read_message(&mut subscriber), "notify 7 (\"temp\",21)");
+    assert_eq!(
+        request(&mut subscriber, "unsubscribe 7"),
+        "Successful request"
+    );
+    request(&mut producer, "out (\"temp\",22)");
+    assert_eq!(
+        request(&mut subscriber, "read (\"wind\",_)"),
+        "(\"wind\",3)"
+    );
+}
+
+#[test]
+fn test_notification_after_revocation() {
+    let (repository, port) = start_server();
+    repository.add_credential("viewer", vec![String::from("viewer")]);
+    let id = repository.grant("sensors", "read", "viewer", None, false);
+    let mut subscriber = attach_with(&port, "viewer");
+    let mut producer = attach(&port);
+    assert_eq!(
+        request(&mut subscriber, "subscribe 7 (\"temp\",_)"),
+        "Successful request"
+    );
+    request(&mut producer, "out (\"temp\",21)");
+    assert_eq!(read_message(&mut subscriber), "notify 7 (\"temp\",21)");
+
+    // The session may no longer read the tuple space once the grant is revoked.
+    assert!(repository.revoke_grant(id));
+    request(&mut producer, "out (\"temp\",22)");
+    assert_eq!(
+        request(&mut subscriber, "unsubscribe 7"),
+        "Successful request"
+    );
+}
+
+#[test]
+fn test_large_response() {
+    let (_, port) = start_server();
+    let mut stream = attach(&port);
+    let value = "x".repeat(16 * 1024);
+    request(&mut stream, &format!("out (\"big\",\"{}\")", value));
+    // A response larger than the socket buffers is kept by the server until the client reads it.
+    let templates = vec!["(\"big\",_)"; 1024].join(",");
+    let read = cipher()
+        .encrypt(
+            Nonce::from_slice(b"unique nonce"),
+            format!("read {}", templates).as_bytes(),
+        )
+        .unwrap();
+    stream.write_all(&read).unwrap();
+    thread::sleep(Duration::from_millis(500));
+    assert_eq!(read_message(&mut stream).matches(&value).count(), 1024);
+    assert_eq!(
+        request(&mut stream, "read (\"big\",_)").len(),
+        value.len() + 10
+    );
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::{repository_with, send, session};
+use rustupolis_server::managed_space::{OverflowPolicy, SpaceLimits};
+use rustupolis_server::repository::Repository;
+use rustupolis_server::session::Session;
+use rustupolis_server::store::StoreKind;
+
+/// Returns a repository and a session attached to its tuple space holding two tuples.
+fn sensors() -> (Repository, Session) {
+    let repository = repository_with(&[("sensors", &["admin"])]);
+    let mut session = session(&["admin"]);
+    send(&repository, &mut session, "attach sensors");
+    send(&repository, &mut session, "out (\"temp\",21),(\"wind\",3)");
+    (repository, session)
+}
+
+#[test]
+fn test_transaction() {
+    let (repository, mut session) = sensors();
+
+    assert_eq!(
+        send(
+            &repository,
+            &mut session,
+            "transaction in (\"temp\",_); out (\"temp\",22); read (\"wind\",_)"
+        ),
+        "(\"temp\",21),(\"wind\",3)"
+    );
+    assert_eq!(
+        send(&repository, &mut session, "read (\"temp\",_)"),
+        "(\"temp\",22)"
+    );
+}
+
+#[test]
+fn test_transaction_rollback() {
+    let (repository, mut session) = sensors();
+
+    // The last operation fails, so the tuples taken are put back and those written removed.
+    assert_eq!(
+        send(
+            &repository,
+            &mut session,
+            "transaction in (\"temp\",_); out (\"rain\",0); in (\"wind\",_); in (\"snow\",_)"
+        ),
+        "ERROR - No matching tuple could be found."
+    );
+    assert_eq!(
+        send(&repository, &mut session, "read (\"temp\",_)"),
+        "(\"temp\",21)"
+    );
+    assert_eq!(
+        send(&repository, &mut session, "read (\"wind\",_)"),
+        "(\"wind\",3)"
+    );
+    assert_eq!(
+        send(&repository, &mut session, "read (\"rain\",_)"),
+        "ERROR - No matching tuple could be found."
+    );
+}
+
+#[test]
+fn test_rollback_restores_dropped_acl() {
+    let repository = Repository::new("admin");
+    repository
+        .add_tuple_space(
+            String::from("latest"),
+            vec![String::from("admin")],
+            StoreKind::default(),
+            SpaceLimits::new(Some(1), None, OverflowPolicy::DropOldest),
+        )
+        .unwrap();
+    let mut session = session(&["admin"]);
+    send(&repository, &mut session, "attach latest");
+    send(&repository, &mut session, "out acl=operator (\"temp\",21)");
+
+    // The secret tuple is dropped to make room, then written back with its readers.
+    assert_eq!(
+        send(
+            &repository,
+            &mut session,
+            "transaction out (\"temp\",22); in (\"wind\",_)"
+        ),
+        "ERROR - No matching tuple could be found."
+    );
+    assert_eq!(
+        send(&repository, &mut session, "read (\"temp\",_)"),
+        "ERROR - No matching tuple could be found."
+    );
+    let mut operator = common::session(&["admin", "operator"]);
+    send(&repository, &mut operator, "attach latest admin operator");
+    assert_eq!(
+        send(&repository, &mut operator, "read (\"temp\",_)"),
+        "(\"temp\",21)"
+    );
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{repository_with, send, session};
+use rustupolis_server::managed_space::{OverflowPolicy, SpaceLimits};
+use rustupolis_server::repository::Repository;
+use rustupolis_server::session::Session;
+use rustupolis_server::store::StoreKind;
+
+/// Returns a repository with a `sensors` tuple space and an `archive` one holding at most two
+/// tuples, and a session attached to `sensors`.
+fn repository() -> (Repository, Session) {
+    let repository = repository_with(&[("sensors", &["admin"])]);
+    repository
+        .add_tuple_space(
+            String::from("archive"),
+            vec![String::from("admin")],
+            StoreKind::default(),
+            SpaceLimits::new(Some(2), None, OverflowPolicy::Reject),
+        )
+        .unwrap();
+    let mut session = session(&["admin"]);
+    send(&repository, &mut session, "attach sensors");
+    (repository, session)
+}
+
+#[test]
+fn test_move_rolled_back() {
+    let (repository, mut session) = repository();
+    send(
+        &repository,
+        &mut session,
+        "out (\"temp\",1),(\"temp\",2),(\"temp\",3)",
+    );
+
+    // The third tuple does not fit into the archive, so none is moved.
+    assert_eq!(
+        send(
+            &repository,
+            &mut session,
+            "move sensors archive (\"temp\",_)"
+        ),
+        "ERROR - The tuple space is full"
+    );
+    assert_eq!(
+        send(
+            &repository,
+            &mut session,
+            "read (\"temp\",1),(\"temp\",2),(\"temp\",3)"
+        ),
+        "(\"temp\",3),(\"temp\",2),(\"temp\",1)"
+    );
+    send(&repository, &mut session, "attach archive");
+    assert_eq!(
+        send(&repository, &mut session, "read (\"temp\",_)"),
+        "ERROR - No matching tuple could be found."
+    );
+}
+
+#[test]
+fn test_move_keeps_ttl() {
+    let (repository, mut session) = repository();
+    send(&repository, &mut session, "out ttl=60 (\"temp\",1)");
+    send(&repository, &mut session, "out (\"wind\",1)");
+    send(
+        &repository,
+        &mut session,
+        "copy sensors archive (\"wind\",_)",
+    );
+    send(
+        &repository,
+        &mut session,
+        "move sensors archive (\"temp\",_)",
+    );
+
+    send(&repository, &mut session, "attach archive");
+    repository.sweep_expirations(Instant::now() + Duration::from_secs(30));
+    assert_eq!(
+        send(&repository, &mut session, "read (\"temp\",_)"),
+        "(\"temp\",1)"
+    );
+    repository.sweep_expirations(Instant::now() + Duration::from_secs(61));
+    assert_eq!(
+        send(&repository, &mut session, "read (\"temp\",_)"),
+        "ERROR - No matching tuple could be found."
+    );
+    assert_eq!(
+        send(&repository, &mut session, "read (\"wind\",_)"),
+        "(\"wind\",1)"
+    );
+}
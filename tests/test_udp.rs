@@ -0,0 +1,88 @@
+mod common;
+
+use std::net::UdpSocket;
+use std::thread;
+use std::time::Duration;
+
+use aes_gcm::aead::{Aead, NewAead};
+use aes_gcm::{Aes128Gcm, Key, Nonce};
+use common::KEY;
+use rustupolis_server::client::Client;
+use rustupolis_server::managed_space::SpaceLimits;
+use rustupolis_server::repository::Repository;
+use rustupolis_server::server::{Protocol, Server};
+use rustupolis_server::store::StoreKind;
+
+/// Starts a UDP server on a free port of the loopback interface, serving a repository with
+/// twenty tuple spaces which the `admin` token may use, and waits until it answers. Returns the
+/// port.
+fn start_server() -> String {
+    let repository: &'static Repository = Box::leak(Box::new(Repository::new("admin")));
+    for index in 0..20 {
+        repository
+            .add_tuple_space(
+                format!("sensors/{}", index),
+                vec![String::from("admin")],
+                StoreKind::default(),
+                SpaceLimits::unlimited(),
+            )
+            .unwrap();
+    }
+    repository.add_credential("token", vec![String::from("admin")]);
+    let port = UdpSocket::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+        .to_string();
+    let server_port = port.clone();
+    thread::spawn(move || {
+        let ip_address = String::from("127.0.0.1");
+        Server::new(Protocol::UDP, &ip_address, &server_port, repository, KEY).start_server()
+    });
+
+    let probe = UdpSocket::bind("127.0.0.1:0").unwrap();
+    probe.connect(format!("127.0.0.1:{}", port)).unwrap();
+    probe
+        .set_read_timeout(Some(Duration::from_millis(50)))
+        .unwrap();
+    let cipher = Aes128Gcm::new(Key::from_slice(KEY.as_bytes()));
+    let request = cipher
+        .encrypt(Nonce::from_slice(b"unique nonce"), b"list".as_ref())
+        .unwrap();
+    let mut buf = [0; 1024];
+    // The datagrams sent before the server binds its socket are refused.
+    for _ in 0..100 {
+        if probe.send(&request).is_ok() && probe.recv(&mut buf).is_ok() {
+            return port;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    panic!("the server does not answer");
+}
+
+#[test]
+fn test_long_response() {
+    let port = start_server();
+    let server_name = String::from("udp");
+    let mut client = Client::new();
+    client.connect(
+        String::from("127.0.0.1"),
+        port,
+        String::from("udp"),
+        &server_name,
+        KEY,
+    );
+    assert!(client.authenticate(&server_name, "token"));
+
+    // The descriptions of twenty tuple spaces take far more than a small buffer, and a
+    // truncated response could not be decrypted.
+    let mut descriptions = client.list(&server_name, vec![String::from("admin")]);
+    let mut count = 0;
+    while !descriptions.is_empty() {
+        count += 1;
+        descriptions = descriptions.rest();
+    }
+    // The permission tuple space and the twenty others.
+    assert_eq!(count, 21);
+}